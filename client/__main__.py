@@ -2,9 +2,11 @@ import argparse
 import json
 
 from lib import App, register
-from watcher import watch
+from watcher import watch, load_secret_key
 from poker import Poker
+from schemes import SCHEMES
 from ui import PokerUI
+from utils import get
 
 CONTRACT = 'poker'
 
@@ -13,7 +15,7 @@ class PokerCli(App):
     @register(name="list", help="List all rooms.")
     def list_all(self):
         rooms = [(int(room['id']), room['name'], room['status'])
-                 for room in self.near.view("all_rooms", {})]
+                 for room in self.near.view("all_rooms", dict(account_id=self.account_id))]
 
         if len(rooms) == 0:
             print("No rooms found.")
@@ -23,20 +25,62 @@ class PokerCli(App):
         for room_id, name, status in rooms:
             print(f"{room_id:>03} {name} {status}")
 
-    @register(help="<name> | Create a new room.")
-    def new_room(self, name):
-        room_id = self.near.change("new_room", dict(name=name))
-        print(f"Created room {name} with id {room_id}")
-
-    @register(help="<room_id> | Enter a room. Can only enter to play in rooms that are Initiating.")
-    def enter(self, room_id):
+    @register(help="<name> <betting_structure> <scheme> | Create a new room. Betting structure is NoLimit (default), PotLimit or FixedLimit. Card scheme is ElGamal (default) or Sra.")
+    def new_room(self, name, betting_structure=None, scheme=None):
+        config = {}
+        if betting_structure is not None:
+            config["betting_structure"] = betting_structure
+        if scheme is not None:
+            config["scheme"] = scheme
+        result = self.near.change(
+            "new_room", dict(name=name, config=config or None))
+        if 'Ok' in result:
+            print(f"Created room {name} with id {result['Ok']}")
+        else:
+            print(result)
+
+    @register(help="<room_id> <amount> <password> | Enter a room buying tokens with amount NEAR. Can only enter to play in rooms that are Initiating. Password is only required on private rooms.")
+    def enter(self, room_id, amount, password=None):
         room_id = int(room_id)
-        result = self.near.change("enter", dict(room_id=room_id))
+        scheme = get(Poker(self.near, room_id).deck_state(), 'Ok', 'scheme')
+        if scheme is None:
+            print(f"Room {room_id} not found.")
+            return
+        key = SCHEMES[scheme].encoded_public_key(
+            load_secret_key(self.near, room_id, scheme))
+        result = self.near.change(
+            "enter", dict(room_id=room_id, public_key={scheme: key}, password=password), amount=amount)
+        if result is not None and 'Err' in result:
+            print(result)
+            return
         self.ui.enter(room_id)
         watch(self.near, room_id, self.ui)
         self.room_id = room_id
 
-    @register(name="start", help="<room_id> | Start the game in a room if it is Initiating or Idle")
+    @register(short="i", help="<room_id> <account_id>... | Make the room private, only the host and these accounts can enter. Only the host can call it.")
+    def invite(self, room_id, *account_ids):
+        room_id = int(room_id)
+        result = self.near.change("set_access", dict(
+            room_id=room_id, access={"Invite": list(account_ids)}))
+        print(result)
+
+    @register(short="v", help="<room_id> | Leave a room between rounds and get back the value of your tokens.")
+    def leave(self, room_id=None):
+        if room_id is None:
+            room_id = self.room_id
+        room_id = int(room_id)
+        result = self.near.change("leave", dict(room_id=room_id))
+        print(result)
+
+    @register(short="y", help="<room_id> | Mark yourself ready to start the next hand.")
+    def ready(self, room_id=None):
+        if room_id is None:
+            room_id = self.room_id
+        room_id = int(room_id)
+        result = self.near.change("ready", dict(room_id=room_id))
+        print(result)
+
+    @register(name="start", help="<room_id> | Start the game in a room if it is Initiating or Idle and all players are ready")
     def _start(self, room_id=None):
         if room_id is None:
             room_id = self.room_id
@@ -44,23 +88,61 @@ class PokerCli(App):
         result = self.near.change("start", dict(room_id=room_id))
         print(result)
 
-    @register(name="raise", help="<amount> <room_id> | Increase your current bet TO amount.")
-    def _raise(self, amount, room_id=None):
+    def _submit_bet_action(self, bet, room_id):
         if room_id is None:
             room_id = self.room_id
         room_id = int(room_id)
-        amount = int(amount)
         result = self.near.change("submit_bet_action", dict(
-            room_id=room_id, bet={"Stake": amount}))
+            room_id=room_id, bet=bet))
         print(result)
 
+    @register(short="k", help="<room_id> | Pass without staking more tokens.")
+    def check(self, room_id=None):
+        self._submit_bet_action("Check", room_id)
+
+    @register(help="<room_id> | Match the current max stake.")
+    def call(self, room_id=None):
+        self._submit_bet_action("Call", room_id)
+
+    @register(help="<amount> <room_id> | Open the betting round staking amount over the current max stake.")
+    def bet(self, amount, room_id=None):
+        self._submit_bet_action({"Bet": int(amount)}, room_id)
+
+    @register(name="raise", help="<amount> <room_id> | Increase your current bet TO amount.")
+    def _raise(self, amount, room_id=None):
+        self._submit_bet_action({"Raise": int(amount)}, room_id)
+
+    @register(help="<room_id> | Stake all your tokens.")
+    def allin(self, room_id=None):
+        self._submit_bet_action("AllIn", room_id)
+
     @register(help="<room_id> | Fold your cards for this round.")
     def fold(self, room_id=None):
+        self._submit_bet_action("Fold", room_id)
+
+    def _submit_showdown_action(self, action, room_id):
         if room_id is None:
             room_id = self.room_id
         room_id = int(room_id)
-        result = self.near.change("submit_bet_action", dict(
-            room_id=room_id, bet="Fold"))
+        result = self.near.change("submit_showdown_action", dict(
+            room_id=room_id, action=action))
+        print(result)
+
+    @register(short="o", help="<room_id> | Show your cards at showdown.")
+    def show(self, room_id=None):
+        self._submit_showdown_action("Show", room_id)
+
+    @register(help="<room_id> | Give up the pot at showdown without showing your cards.")
+    def muck(self, room_id=None):
+        self._submit_showdown_action("Muck", room_id)
+
+    @register(short="g", help="<yes|no> <room_id> | Decide whether to straddle twice the big blind before the cards are dealt.")
+    def straddle(self, answer, room_id=None):
+        if room_id is None:
+            room_id = self.room_id
+        room_id = int(room_id)
+        result = self.near.change("submit_straddle", dict(
+            room_id=room_id, straddle=answer == "yes"))
         print(result)
 
     @register(short="t", help="<room_id> | Show game state.")
@@ -87,6 +169,22 @@ class PokerCli(App):
         result = self.near.view("poker_state", dict(room_id=room_id))
         print(result)
 
+    @register(short="x", name="timeout", help="<room_id> | Unblock the game if the player in turn didn't act before the deadline.")
+    def claim_timeout(self, room_id=None):
+        if room_id is None:
+            room_id = self.room_id
+        room_id = int(room_id)
+        result = self.near.change("claim_timeout", dict(room_id=room_id))
+        print(result)
+
+    @register(help="<room_id> | Retry payouts that failed.")
+    def withdraw(self, room_id=None):
+        if room_id is None:
+            room_id = self.room_id
+        room_id = int(room_id)
+        result = self.near.change("withdraw", dict(room_id=room_id))
+        print(result)
+
 
 if __name__ == '__main__':
     parser = argparse.ArgumentParser('Poker game')
@@ -1,13 +1,19 @@
+import hashlib
 import random
 
-# Some primer numbers of different magnitudes.
-# 1000000007
-# 1000000000039
-# 1000000000000000003
-# 1000000000000000000000007
-# 1000000000000000000000000000057
-# Big prime number. Increase it for more security.
-MOD = 1000000000000000003
+# Safe prime. Cards are encrypted in the subgroup of quadratic residues modulo MOD,
+# which has prime order ORDER. Should match the contract.
+MOD = 2**256 - 36113
+ORDER = (MOD - 1) // 2
+
+# Generator of the group. Public keys are GENERATOR^secret_key.
+GENERATOR = 2
+
+# Cards are shifted by this amount before being encoded. Should match the contract.
+CARD_OFFSET = 2
+
+# Label of the generators used to commit to a permutation. Should match the contract.
+GENERATOR_LABEL = b"poker shuffle generator"
 
 
 def extended_gcd(a, b):
@@ -25,10 +31,19 @@ def extended_gcd(a, b):
 
 
 def generate_secret_key():
-    while True:
-        sk = random.randint(100, MOD - 1)
-        if extended_gcd(sk, MOD - 1)[0] == 1:
-            return sk
+    return random.SystemRandom().randint(2, ORDER - 1)
+
+
+def encode_card(card):
+    return pow(card + CARD_OFFSET, 2, MOD)
+
+
+def decode_card(value, num_cards=52):
+    value = int(value)
+    for card in range(num_cards):
+        if encode_card(card) == value:
+            return card
+    return None
 
 
 def inverse(a, mod):
@@ -37,12 +52,169 @@ def inverse(a, mod):
     return (x % mod + mod) % mod
 
 
+def public_key(secret_key):
+    return pow(GENERATOR, secret_key, MOD)
+
+
+def _challenge(parts):
+    transcript = ";".join(",".join(str(value) for value in part)
+                          for part in parts)
+    return hashlib.sha256(transcript.encode()).digest()
+
+
+def _hash_values(seed, *parts):
+    """sha256 of seed followed by every value as 32 bytes little endian."""
+    data = seed + b"".join(value.to_bytes(32, 'little')
+                           for part in parts for value in part)
+    return hashlib.sha256(data).digest()
+
+
+def _derive_challenge(seed, index):
+    digest = hashlib.sha256(seed + index.to_bytes(8, 'little')).digest()
+    return int.from_bytes(digest[:16], 'little')
+
+
+def commitment_generators(count):
+    """Generators h, h_0, .., h_(count-1) used to commit to a permutation."""
+    generators = []
+    for index in range(count + 1):
+        digest = hashlib.sha256(
+            GENERATOR_LABEL + index.to_bytes(8, 'little')).digest()
+        generators.append(pow(int.from_bytes(digest, 'little'), 2, MOD))
+    return generators
+
+
+def _random_exponent():
+    return random.SystemRandom().randint(0, ORDER - 1)
+
+
+def _product(values):
+    result = 1
+    for value in values:
+        result = result * value % MOD
+    return result
+
+
 def encrypt_and_shuffle(partial_shuffle, secret_key):
-    partial_shuffle = [pow(num, secret_key, MOD) for num in partial_shuffle]
-    random.shuffle(partial_shuffle)
-    return partial_shuffle
+    """
+    Encrypt and shuffle the cards. Return the new cards and a proof that they are a
+    permutation of the previous cards encrypted with secret_key, with the shuffle
+    argument described in the contract.
+    """
+    n = len(partial_shuffle)
+    # new_cards[i] = partial_shuffle[permutation[i]] ^ secret_key
+    permutation = list(range(n))
+    random.SystemRandom().shuffle(permutation)
+    new_cards = [pow(partial_shuffle[j], secret_key, MOD) for j in permutation]
+    generators = commitment_generators(n)
+
+    r = [_random_exponent() for _ in range(n)]
+    permutation_commitments = [0] * n
+    for i, j in enumerate(permutation):
+        permutation_commitments[j] = pow(GENERATOR, r[j], MOD) * generators[i + 1] % MOD
+
+    seed = _hash_values(b"", [public_key(secret_key)], partial_shuffle, new_cards,
+                        permutation_commitments)
+    u = [_derive_challenge(seed, j) for j in range(n)]
+    v = [u[j] for j in permutation]
+
+    s = [_random_exponent() for _ in range(n)]
+    chain = []
+    for i in range(n):
+        last = chain[-1] if chain else generators[0]
+        chain.append(pow(GENERATOR, s[i], MOD) * pow(last, v[i], MOD) % MOD)
+
+    w = [_random_exponent() for _ in range(4)]
+    w_chain = [_random_exponent() for _ in range(n)]
+    w_permuted = [_random_exponent() for _ in range(n)]
+    a = _product(pow(card, u_j, MOD) for card, u_j in zip(partial_shuffle, u))
+    commitments = [
+        pow(GENERATOR, w[0], MOD),
+        pow(GENERATOR, w[1], MOD),
+        pow(GENERATOR, w[2], MOD) *
+        _product(pow(h, w_i, MOD)
+                 for h, w_i in zip(generators[1:], w_permuted)) % MOD,
+        _product(pow(card, w_i, MOD) for card, w_i in zip(new_cards, w_permuted)) *
+        pow(a, -w[3] % ORDER, MOD) % MOD,
+        pow(GENERATOR, w[3], MOD),
+    ]
+    chain_commitments = [
+        pow(GENERATOR, w_chain[i], MOD) *
+        pow(chain[i - 1] if i else generators[0], w_permuted[i], MOD) % MOD
+        for i in range(n)]
+
+    challenge_hash = _hash_values(seed, chain, commitments, chain_commitments)
+    c = _derive_challenge(challenge_hash, 0)
+
+    chain_randomness = 0
+    for s_i, v_i in zip(s, v):
+        chain_randomness = (chain_randomness * v_i + s_i) % ORDER
+    responses = [
+        (w[0] + c * sum(r)) % ORDER,
+        (w[1] + c * chain_randomness) % ORDER,
+        (w[2] + c * sum(r_j * u_j for r_j, u_j in zip(r, u))) % ORDER,
+        (w[3] + c * secret_key) % ORDER,
+    ]
+
+    def encode(values):
+        return [str(value) for value in values]
+
+    proof = {"permutation_commitments": encode(permutation_commitments),
+             "chain": encode(chain),
+             "commitments": encode(commitments),
+             "chain_commitments": encode(chain_commitments),
+             "responses": encode(responses),
+             "chain_responses": encode((w_i + c * s_i) % ORDER for w_i, s_i in zip(w_chain, s)),
+             "permuted_responses": encode((w_i + c * v_i) % ORDER for w_i, v_i in zip(w_permuted, v))}
+    return new_cards, proof
 
 
 def partial_decrypt(progress, secret_key):
-    pw = inverse(secret_key, MOD - 1)
+    pw = inverse(secret_key, ORDER)
     return pow(progress, pw, MOD)
+
+
+def prove_partial_decrypt(progress, part, secret_key):
+    """
+    Chaum-Pedersen proof that progress = part^secret_key, using the same key of the
+    public key.
+    """
+    w = random.SystemRandom().randint(0, ORDER - 1)
+    base_commitment = pow(GENERATOR, w, MOD)
+    card_commitment = pow(part, w, MOD)
+    digest = _challenge([[GENERATOR, public_key(secret_key), part,
+                          progress, base_commitment, card_commitment]])
+    challenge = int.from_bytes(digest[:16], 'little')
+    response = (w + challenge * secret_key) % ORDER
+    return {"base_commitment": str(base_commitment),
+            "card_commitment": str(card_commitment),
+            "response": str(response)}
+
+
+def shuffle(partial_shuffle, public_keys, secret_key):
+    """Shuffle the cards as returned by the contract. Return new cards and proof."""
+    new_cards, proof = encrypt_and_shuffle(
+        [int(card) for card in partial_shuffle], secret_key)
+    return [str(card) for card in new_cards], proof
+
+
+def reveal_part(progress, secret_key):
+    """Card decrypted with secret_key and the proof that it was done correctly."""
+    progress = int(progress)
+    part = partial_decrypt(progress, secret_key)
+    return str(part), prove_partial_decrypt(progress, part, secret_key)
+
+
+def receive_card(progress, secret_key):
+    """Card once every other player decrypted it."""
+    return decode_card(partial_decrypt(int(progress), secret_key))
+
+
+def encoded_public_key(secret_key):
+    """Public key in the format expected by the contract."""
+    return str(public_key(secret_key))
+
+
+def encoded_secret_key(secret_key):
+    """Secret key in the format expected by the contract to audit a hand."""
+    return str(secret_key)
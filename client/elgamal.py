@@ -0,0 +1,374 @@
+import hashlib
+import random
+
+# Threshold ElGamal over Ristretto255. Should match the contract.
+
+# Field of the curve edwards25519.
+P = 2**255 - 19
+# Order of the group.
+L = 2**252 + 27742317777372353535851937790883648493
+
+D = -121665 * pow(121666, P - 2, P) % P
+SQRT_M1 = pow(2, (P - 1) // 4, P)
+
+
+def _is_negative(x):
+    return x % P & 1
+
+
+def _abs(x):
+    return (P - x) % P if _is_negative(x) else x % P
+
+
+def _sqrt_ratio_m1(u, v):
+    r = u * pow(v, 3, P) * pow(u * pow(v, 7, P), (P - 5) // 8, P) % P
+    check = v * r * r % P
+    correct_sign = check == u % P
+    flipped_sign = check == (-u) % P
+    flipped_sign_i = check == (-u * SQRT_M1) % P
+    if flipped_sign or flipped_sign_i:
+        r = r * SQRT_M1 % P
+    return correct_sign or flipped_sign, _abs(r)
+
+
+INVSQRT_A_MINUS_D = _sqrt_ratio_m1(1, (-1 - D) % P)[1]
+# Constants of the ristretto255 map from uniform bytes, RFC 9496.
+SQRT_AD_MINUS_ONE = 25063068953384623474111414158702152701244531502492656460079210482610430750235
+ONE_MINUS_D_SQ = (1 - D * D) % P
+D_MINUS_ONE_SQ = (D - 1) * (D - 1) % P
+
+# Label of the generators used to commit to a permutation. Should match the contract.
+GENERATOR_LABEL = b"poker shuffle generator"
+
+
+class Point:
+    """Point of edwards25519 in extended coordinates, representing a Ristretto255 element."""
+
+    def __init__(self, x, y, z, t):
+        self.x, self.y, self.z, self.t = x, y, z, t
+
+    def __add__(self, other):
+        a = (self.y - self.x) * (other.y - other.x) % P
+        b = (self.y + self.x) * (other.y + other.x) % P
+        c = 2 * D * self.t * other.t % P
+        d = 2 * self.z * other.z % P
+        e, f, g, h = b - a, d - c, d + c, b + a
+        return Point(e * f % P, g * h % P, f * g % P, e * h % P)
+
+    def __neg__(self):
+        return Point((-self.x) % P, self.y, self.z, (-self.t) % P)
+
+    def __sub__(self, other):
+        return self + (-other)
+
+    def __mul__(self, scalar):
+        result, addend = IDENTITY, self
+        scalar %= L
+        while scalar > 0:
+            if scalar & 1:
+                result = result + addend
+            addend = addend + addend
+            scalar >>= 1
+        return result
+
+    def __eq__(self, other):
+        return (self.x * other.y - self.y * other.x) % P == 0 or \
+            (self.y * other.y - self.x * other.x) % P == 0
+
+    def encode(self):
+        u1 = (self.z + self.y) * (self.z - self.y) % P
+        u2 = self.x * self.y % P
+        _, invsqrt = _sqrt_ratio_m1(1, u1 * u2 * u2 % P)
+        den1 = invsqrt * u1 % P
+        den2 = invsqrt * u2 % P
+        z_inv = den1 * den2 * self.t % P
+        if _is_negative(self.t * z_inv):
+            x, y = self.y * SQRT_M1 % P, self.x * SQRT_M1 % P
+            den_inv = den1 * INVSQRT_A_MINUS_D % P
+        else:
+            x, y = self.x, self.y
+            den_inv = den2
+        if _is_negative(x * z_inv):
+            y = -y
+        s = _abs(den_inv * (self.z - y))
+        return s.to_bytes(32, 'little').hex()
+
+    @staticmethod
+    def decode(encoded):
+        s = int.from_bytes(bytes.fromhex(encoded), 'little')
+        assert s < P and not _is_negative(s), "Invalid point"
+        ss = s * s % P
+        u1 = (1 - ss) % P
+        u2 = (1 + ss) % P
+        u2_sqr = u2 * u2 % P
+        v = (-(D * u1 * u1) - u2_sqr) % P
+        was_square, invsqrt = _sqrt_ratio_m1(1, v * u2_sqr % P)
+        den_x = invsqrt * u2 % P
+        den_y = invsqrt * den_x * v % P
+        x = _abs(2 * s * den_x)
+        y = u1 * den_y % P
+        t = x * y % P
+        assert was_square and not _is_negative(t) and y != 0, "Invalid point"
+        return Point(x, y, 1, t)
+
+    @staticmethod
+    def _elligator(r0):
+        r = SQRT_M1 * r0 * r0 % P
+        u = (r + 1) * ONE_MINUS_D_SQ % P
+        v = (-1 - r * D) * (r + D) % P
+        was_square, s = _sqrt_ratio_m1(u, v)
+        if was_square:
+            c = P - 1
+        else:
+            s = (-_abs(s * r0)) % P
+            c = r
+        n = (c * (r - 1) * D_MINUS_ONE_SQ - v) % P
+        w0 = 2 * s * v % P
+        w1 = n * SQRT_AD_MINUS_ONE % P
+        w2 = (1 - s * s) % P
+        w3 = (1 + s * s) % P
+        return Point(w0 * w3 % P, w2 * w1 % P, w1 * w3 % P, w0 * w2 % P)
+
+    @staticmethod
+    def from_uniform_bytes(data):
+        """Point mapped from 64 uniform bytes, as ristretto255 hash to group."""
+        def field_element(chunk):
+            return (int.from_bytes(chunk, 'little') & ((1 << 255) - 1)) % P
+        return Point._elligator(field_element(data[:32])) + \
+            Point._elligator(field_element(data[32:]))
+
+
+IDENTITY = Point(0, 1, 1, 0)
+GENERATOR = Point.decode(
+    "e2f2ae0a6abc4e71a884a961c500515f58e30b6aa582dd8db6a65945e08d2d76")
+
+
+class FixedBase:
+    """Precomputed multiples of a point to speed up multiplications by it."""
+
+    def __init__(self, point):
+        self.table = []
+        for _ in range(64):
+            row = [IDENTITY]
+            for _ in range(15):
+                row.append(row[-1] + point)
+            self.table.append(row)
+            point = row[-1] + point
+
+    def __mul__(self, scalar):
+        scalar %= L
+        result = IDENTITY
+        for row in self.table:
+            result = result + row[scalar & 15]
+            scalar >>= 4
+        return result
+
+
+_GENERATOR_TABLE = None
+
+
+def _generator_table():
+    global _GENERATOR_TABLE
+    if _GENERATOR_TABLE is None:
+        _GENERATOR_TABLE = FixedBase(GENERATOR)
+    return _GENERATOR_TABLE
+
+
+def _random_scalar():
+    return random.SystemRandom().randint(1, L - 1)
+
+
+def _scalar_hex(scalar):
+    return (scalar % L).to_bytes(32, 'little').hex()
+
+
+def _challenge(encoded_points):
+    digest = hashlib.sha256(
+        b''.join(bytes.fromhex(point) for point in encoded_points)).digest()
+    return int.from_bytes(digest, 'little') % L
+
+
+def generate_secret_key():
+    return _random_scalar()
+
+
+def encoded_public_key(secret_key):
+    """
+    Public key with a Schnorr proof that the secret key is known, in the format
+    expected by the contract.
+    """
+    key = (_generator_table() * secret_key).encode()
+    w = _random_scalar()
+    commitment = (_generator_table() * w).encode()
+    challenge = _challenge([GENERATOR.encode(), key, commitment])
+    return {"key": key,
+            "commitment": commitment,
+            "response": _scalar_hex(w + challenge * secret_key)}
+
+
+def encoded_secret_key(secret_key):
+    """Secret key in the format expected by the contract to audit a hand."""
+    return _scalar_hex(secret_key)
+
+
+def encode_card(card):
+    return (_generator_table() * (card + 1)).encode()
+
+
+def decode_card(card, num_cards=52):
+    for index in range(num_cards):
+        if encode_card(index) == card["value"]:
+            return index
+    return None
+
+
+def _remask(card, randomness, joint_table):
+    mask, value = card
+    return mask + _generator_table() * randomness, value + joint_table * randomness
+
+
+def _encode_cards(cards):
+    return [{"mask": mask.encode(), "value": value.encode()} for mask, value in cards]
+
+
+def _card_bytes(cards):
+    return b"".join(bytes.fromhex(card["mask"]) + bytes.fromhex(card["value"])
+                    for card in cards)
+
+
+def _derive_challenge(seed, index):
+    digest = hashlib.sha256(seed + index.to_bytes(8, 'little')).digest()
+    return int.from_bytes(digest[:16], 'little')
+
+
+def commitment_generators(count):
+    """Generators h, h_0, .., h_(count-1) used to commit to a permutation."""
+    generators = []
+    for index in range(count + 1):
+        first = hashlib.sha256(
+            GENERATOR_LABEL + index.to_bytes(8, 'little')).digest()
+        generators.append(Point.from_uniform_bytes(
+            first + hashlib.sha256(first).digest()))
+    return generators
+
+
+def _sum(points):
+    result = IDENTITY
+    for point in points:
+        result = result + point
+    return result
+
+
+def shuffle(partial_shuffle, public_keys, secret_key):
+    """
+    Mask again and shuffle the cards with the joint key of every player. Return the new
+    cards and a proof that they are a permutation of the previous cards, with the shuffle
+    argument described in the contract.
+    """
+    joint_key = IDENTITY
+    for key in public_keys:
+        joint_key = joint_key + Point.decode(key["key"])
+    joint_table = FixedBase(joint_key)
+    base = _generator_table()
+
+    n = len(partial_shuffle)
+    previous = [(Point.decode(card["mask"]), Point.decode(card["value"]))
+                for card in partial_shuffle]
+    # new_cards[i] = previous[permutation[i]] masked again with masks[i]
+    permutation = list(range(n))
+    random.SystemRandom().shuffle(permutation)
+    masks = [_random_scalar() for _ in range(n)]
+    new_points = [_remask(previous[j], m, joint_table)
+                  for j, m in zip(permutation, masks)]
+    new_cards = _encode_cards(new_points)
+    generators = commitment_generators(n)
+
+    r = [_random_scalar() for _ in range(n)]
+    permutation_commitments = [None] * n
+    for i, j in enumerate(permutation):
+        permutation_commitments[j] = (base * r[j] + generators[i + 1]).encode()
+
+    seed = hashlib.sha256(bytes.fromhex(joint_key.encode()) + _card_bytes(partial_shuffle) +
+                          _card_bytes(new_cards) +
+                          b"".join(bytes.fromhex(point) for point in permutation_commitments)).digest()
+    u = [_derive_challenge(seed, j) for j in range(n)]
+    v = [u[j] for j in permutation]
+
+    s = [_random_scalar() for _ in range(n)]
+    chain = []
+    for i in range(n):
+        last = chain[-1] if chain else generators[0]
+        chain.append(base * s[i] + last * v[i])
+
+    w = [_random_scalar() for _ in range(4)]
+    w_chain = [_random_scalar() for _ in range(n)]
+    w_permuted = [_random_scalar() for _ in range(n)]
+    commitments = [
+        (base * w[0]).encode(),
+        (base * w[1]).encode(),
+        (base * w[2] + _sum(h * w_i for h,
+         w_i in zip(generators[1:], w_permuted))).encode(),
+    ]
+    deck_commitment = {
+        "mask": (_sum(mask * w_i for (mask, _), w_i in zip(new_points, w_permuted)) -
+                 base * w[3]).encode(),
+        "value": (_sum(value * w_i for (_, value), w_i in zip(new_points, w_permuted)) -
+                  joint_table * w[3]).encode(),
+    }
+    chain_commitments = [
+        (base * w_chain[i] + (chain[i - 1] if i else generators[0]) * w_permuted[i]).encode()
+        for i in range(n)]
+    chain = [link.encode() for link in chain]
+
+    challenge_hash = hashlib.sha256(
+        seed + b"".join(bytes.fromhex(point) for point in chain + commitments) +
+        _card_bytes([deck_commitment]) +
+        b"".join(bytes.fromhex(point) for point in chain_commitments)).digest()
+    c = _derive_challenge(challenge_hash, 0)
+
+    chain_randomness = 0
+    for s_i, v_i in zip(s, v):
+        chain_randomness = (chain_randomness * v_i + s_i) % L
+
+    proof = {"permutation_commitments": permutation_commitments,
+             "chain": chain,
+             "commitments": commitments,
+             "deck_commitment": deck_commitment,
+             "chain_commitments": chain_commitments,
+             "responses": [
+                 _scalar_hex(w[0] + c * sum(r)),
+                 _scalar_hex(w[1] + c * chain_randomness),
+                 _scalar_hex(w[2] + c * sum(r_j * u_j for r_j, u_j in zip(r, u))),
+                 _scalar_hex(w[3] + c * sum(m_i * v_i for m_i, v_i in zip(masks, v))),
+             ],
+             "chain_responses": [_scalar_hex(w_i + c * s_i) for w_i, s_i in zip(w_chain, s)],
+             "permuted_responses": [_scalar_hex(w_i + c * v_i)
+                                    for w_i, v_i in zip(w_permuted, v)]}
+    return new_cards, proof
+
+
+def _share(progress, secret_key):
+    return Point.decode(progress["mask"]) * secret_key
+
+
+def reveal_part(progress, secret_key):
+    """
+    Decryption share of the card and a Chaum-Pedersen proof that it was computed with
+    the same key of the public key.
+    """
+    mask = Point.decode(progress["mask"])
+    share = (mask * secret_key).encode()
+    w = _random_scalar()
+    base_commitment = (_generator_table() * w).encode()
+    card_commitment = (mask * w).encode()
+    challenge = _challenge([GENERATOR.encode(), (_generator_table() * secret_key).encode(),
+                            progress["mask"], share, base_commitment, card_commitment])
+    return share, {"base_commitment": base_commitment,
+                   "card_commitment": card_commitment,
+                   "response": _scalar_hex(w + challenge * secret_key)}
+
+
+def receive_card(progress, secret_key):
+    """Card once every other player removed its share."""
+    value = Point.decode(progress["value"]) - _share(progress, secret_key)
+    return decode_card({"value": value.encode()})
@@ -79,7 +79,7 @@ class Near:
         else:
             logging.warn(f"Command stdout: {proc.stdout.read().decode()}")
 
-    def change(self, name, args={}):
+    def change(self, name, args={}, amount=None):
         command = [
             "near",
             "call",
@@ -91,6 +91,8 @@ class Near:
             "--accountId",
             self.account_id
         ]
+        if amount is not None:
+            command.extend(["--amount", str(amount)])
         command = self.add_command_url(command)
 
         logging.debug(f"Change Command: {command}")
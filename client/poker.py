@@ -13,9 +13,13 @@ class Poker:
     def state(self):
         pass
 
-    @view_function
     def deck_state(self):
-        pass
+        # The deck is tagged with the card scheme of the room.
+        result = self.near.view("deck_state", dict(room_id=self.room_id))
+        if not isinstance(result, dict) or not isinstance(result.get('Ok'), dict):
+            return result
+        (scheme, deck), = result['Ok'].items()
+        return {'Ok': dict(deck, scheme=scheme)}
 
     @view_function
     def poker_state(self):
@@ -29,13 +33,17 @@ class Poker:
     def get_turn(self):
         pass
 
-    def submit_partial_shuffle(self, partial_shuffle):
+    def submit_partial_shuffle(self, scheme, partial_shuffle, proof):
         self.near.change("submit_shuffled", dict(
-            room_id=self.room_id, new_cards=partial_shuffle))
+            room_id=self.room_id, new_cards={scheme: partial_shuffle}, proof={scheme: proof}))
 
-    def submit_reveal_part(self, progress):
+    def submit_reveal_part(self, scheme, part, proof):
         self.near.change("submit_reveal_part", dict(
-            room_id=self.room_id, card=progress))
+            room_id=self.room_id, part={scheme: part}, proof={scheme: proof}))
+
+    def submit_secret_key(self, scheme, secret_key, public_key):
+        return self.near.change("submit_secret_key", dict(
+            room_id=self.room_id, secret_key={scheme: secret_key}, public_key={scheme: public_key}))
 
     def finish_reveal(self):
         self.near.change("finish_reveal", dict(room_id=self.room_id))
@@ -0,0 +1,7 @@
+import cryptography
+import elgamal
+
+# Implementation of each card scheme supported by the contract. Every module provides
+# generate_secret_key, encoded_public_key, shuffle, reveal_part, receive_card and
+# decode_card.
+SCHEMES = {"Sra": cryptography, "ElGamal": elgamal}
@@ -1,5 +1,6 @@
 import io
 from lib import logging
+from schemes import SCHEMES
 from utils import get
 
 SUITES = '♥♠♦♣'
@@ -101,7 +102,7 @@ class PokerUI:
         self.display()
 
     def get_action(self):
-        g_state = get(self.state, 'Ok')
+        g_state = get(self.state, 'Ok', 'status')
 
         if g_state == 'PokerAction':
             return parse_id(get(self.poker_state, 'Ok', 'status'))
@@ -120,10 +121,11 @@ class PokerUI:
 
         total_players = len(get(self.deck_state, 'Ok', 'players'))
 
+        scheme = SCHEMES[get(self.deck_state, 'Ok', 'scheme')]
         cards = []
         for card in res[2 * total_players:]:
             if card is not None:
-                cards.append(card)
+                cards.append(scheme.decode_card(card))
         return cards
 
     def display(self, force=True):
@@ -135,13 +137,15 @@ class PokerUI:
             tokens = get(self.poker_state, 'Ok', 'tokens')
             staked = get(self.poker_state, 'Ok', 'staked')
             folded = get(self.poker_state, 'Ok', 'folded')
+            ready = get(self.state, 'Ok', 'ready')
 
             action = self.get_action()
             turn = self.turn
 
             if len(players) > 0:
                 tables = [
-                    ["Name", "Cards", "Total", "Staked", "On Game", "Turn"],
+                    ["Name", "Cards", "Total", "Staked",
+                        "On Game", "Ready", "Turn"],
                 ]
 
                 # TODO: Show revealed cards after showdown.
@@ -160,11 +164,11 @@ class PokerUI:
                         cards = ""
 
                     row = [player, cards, tokens[ix],
-                           staked[ix], not folded[ix], ""]
+                           staked[ix], not folded[ix], ready[ix], ""]
 
                     if turn == ix:
                         turn_by_player = True
-                        row[5] = action
+                        row[6] = action
 
                     tables.append(row)
 
@@ -4,11 +4,26 @@ import time
 import os
 import hashlib
 
-from cryptography import encrypt_and_shuffle, partial_decrypt, generate_secret_key
 from poker import Poker
+from schemes import SCHEMES
 from utils import load, dump, get
 
 
+def filename(near, room_id, mode):
+    node_env = os.environ.get("NODE_ENV", "")
+    chain_enc = f"{near.node_url}-{near.contract}-{node_env}"
+    suffix = hashlib.md5(chain_enc.encode()).hexdigest()[:8]
+    return f"{near.account_id}-{room_id}-{mode}-{suffix}"
+
+
+def load_secret_key(near, room_id, scheme):
+    secret_key = load(filename(near, room_id, "secret_key")
+                      ) or SCHEMES[scheme].generate_secret_key()
+    secret_key = int(secret_key)
+    dump(filename(near, room_id, "secret_key"), secret_key)
+    return secret_key
+
+
 class PokerRoomWatcher(threading.Thread):
     def __init__(self, near, room_id, ui):
         self.ui = ui
@@ -26,10 +41,9 @@ class PokerRoomWatcher(threading.Thread):
         self.ui.cards = self.cards[:]
 
         # Load secret key
-        self.secret_key = load(self.filename(
-            "secret_key")) or generate_secret_key()
-        self.secret_key = int(self.secret_key)
-        dump(self.filename("secret_key"), self.secret_key)
+        self.scheme = get(self.poker.deck_state(), 'Ok', 'scheme')
+        self.secret_key = load_secret_key(
+            self.near, self.room_id, self.scheme)
 
     def find_player_id(self):
         players = get(self.poker.deck_state(), 'Ok', 'players')
@@ -50,11 +64,16 @@ class PokerRoomWatcher(threading.Thread):
         self._deck_state = self.poker.deck_state()
         self._poker_state = self.poker.poker_state()
         self._turn = self.poker.get_turn()
+
+        # Seats move when other players leave the room.
+        players = get(self._deck_state, 'Ok', 'players') or []
+        if self.near.account_id in players:
+            self.player_id = players.index(self.near.account_id)
         self.ui.update_state(self.room_id, self._state,
                              self._deck_state, self._poker_state, self._turn)
 
     def is_deck_action(self):
-        return get(self._state, 'Ok') == 'DeckAction'
+        return get(self._state, 'Ok', 'status') == 'DeckAction'
 
     def check_deck_shuffling(self):
         if not self.is_deck_action():
@@ -69,18 +88,14 @@ class PokerRoomWatcher(threading.Thread):
         if index != self.player_id:
             return
 
-        partial_shuffle = self.poker.get_partial_shuffle()["Ok"]
-        delta = 2 if self.player_id == 0 else 0
-        partial_shuffle = [int(value) + delta for value in partial_shuffle]
-        partial_shuffle = encrypt_and_shuffle(partial_shuffle, self.secret_key)
-        partial_shuffle = [str(value) for value in partial_shuffle]
-        self.poker.submit_partial_shuffle(partial_shuffle)
+        partial_shuffle = self.poker.get_partial_shuffle()["Ok"][self.scheme]
+        public_keys = get(self._deck_state, 'Ok', 'public_keys')
+        partial_shuffle, proof = SCHEMES[self.scheme].shuffle(
+            partial_shuffle, public_keys, self.secret_key)
+        self.poker.submit_partial_shuffle(self.scheme, partial_shuffle, proof)
 
     def filename(self, mode):
-        node_env = os.environ.get("NODE_ENV", "")
-        chain_enc = f"{self.near.node_url}-{self.near.contract}-{node_env}"
-        suffix = hashlib.md5(chain_enc.encode()).hexdigest()[:8]
-        return f"{self.near.account_id}-{self.room_id}-{mode}-{suffix}"
+        return filename(self.near, self.room_id, mode)
 
     def on_receive_card(self, card):
         if card in self.cards:
@@ -104,16 +119,41 @@ class PokerRoomWatcher(threading.Thread):
         if index != self.player_id:
             return
 
-        progress = int(get(self._deck_state, 'Ok',
-                           'status', 'Revealing', 'progress'))
-
-        progress = str(partial_decrypt(progress, self.secret_key))
+        progress = get(self._deck_state, 'Ok', 'status', 'Revealing', 'progress')
+        scheme = SCHEMES[self.scheme]
 
         if get(self._deck_state, 'Ok', 'status', 'Revealing', 'receiver') == self.player_id:
-            self.on_receive_card(int(progress) - 2)
+            self.on_receive_card(scheme.receive_card(progress, self.secret_key))
             self.poker.finish_reveal()
         else:
-            self.poker.submit_reveal_part(progress)
+            part, proof = scheme.reveal_part(progress, self.secret_key)
+            self.poker.submit_reveal_part(self.scheme, part, proof)
+
+    def check_audit(self):
+        """
+        Reveal the secret key used in the last hand so it can be audited, and replace it
+        with a new one for the next hands.
+        """
+        if get(self._deck_state, 'Ok', 'status') != 'Closed':
+            return
+
+        players = get(self._deck_state, 'Ok', 'audit', 'players') or []
+        if not self.near.account_id in players:
+            return
+
+        position = players.index(self.near.account_id)
+        if get(self._deck_state, 'Ok', 'audit', 'secret_keys')[position] is not None:
+            return
+
+        scheme = SCHEMES[self.scheme]
+        new_secret_key = scheme.generate_secret_key()
+        result = self.poker.submit_secret_key(
+            self.scheme, scheme.encoded_secret_key(self.secret_key),
+            scheme.encoded_public_key(new_secret_key))
+
+        if isinstance(result, dict) and 'Ok' in result:
+            self.secret_key = new_secret_key
+            dump(self.filename("secret_key"), new_secret_key)
 
     def step(self):
         if self.player_id is None:
@@ -123,6 +163,7 @@ class PokerRoomWatcher(threading.Thread):
         self.update_state()
         self.check_deck_shuffling()
         self.check_revealing()
+        self.check_audit()
 
     def run(self):
         time_to_sleep = 1.
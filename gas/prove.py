@@ -0,0 +1,43 @@
+"""
+Keys and shuffles for the gas measurements, computed with the python client.
+
+    prove.py key <scheme>     prints {"secret_key", "public_key"}
+    prove.py shuffle          reads {"scheme", "secret_key", "cards", "public_keys"} from
+                              stdin and prints {"new_cards", "proof"}
+"""
+import json
+import os
+import sys
+
+sys.dont_write_bytecode = True
+sys.path.insert(0, os.path.join(os.path.dirname(__file__), "..", "client"))
+
+import cryptography  # noqa: E402
+import elgamal  # noqa: E402
+
+SCHEMES = {"Sra": cryptography, "ElGamal": elgamal}
+
+
+def decode_secret_key(scheme, secret_key):
+    if scheme == "Sra":
+        return int(secret_key)
+    return int.from_bytes(bytes.fromhex(secret_key), 'little')
+
+
+def main():
+    if sys.argv[1] == "key":
+        module = SCHEMES[sys.argv[2]]
+        secret_key = module.generate_secret_key()
+        print(json.dumps({"secret_key": module.encoded_secret_key(secret_key),
+                          "public_key": module.encoded_public_key(secret_key)}))
+    else:
+        request = json.load(sys.stdin)
+        scheme = request["scheme"]
+        new_cards, proof = SCHEMES[scheme].shuffle(
+            request["cards"], request["public_keys"],
+            decode_secret_key(scheme, request["secret_key"]))
+        print(json.dumps({"new_cards": new_cards, "proof": proof}))
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,156 @@
+//! Gas burnt by `submit_shuffled` for a full deck with each card scheme, measured in a
+//! sandbox node running the compiled contract. Build the contract first with
+//! `./scripts/build.sh`, then run from this directory with
+//! `cargo test -- --nocapture`.
+
+use near_workspaces::network::Sandbox;
+use near_workspaces::types::{Gas, NearToken};
+use near_workspaces::{Account, Contract, Worker};
+use serde_json::{json, Value};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+const NUM_PLAYERS: usize = 3;
+
+/// Gas attached to a function call, the most a transaction can use.
+const MAX_GAS: Gas = Gas::from_tgas(300);
+
+/// Run `prove.py` with the python client and parse its output.
+fn prove(args: &[&str], input: Option<&Value>) -> anyhow::Result<Value> {
+    let mut child = Command::new("python3")
+        .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/prove.py"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    if let Some(input) = input {
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(input.to_string().as_bytes())?;
+    } else {
+        drop(child.stdin.take());
+    }
+    let output = child.wait_with_output()?;
+    anyhow::ensure!(output.status.success(), "prove.py {:?} failed", args);
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+async fn view(contract: &Contract, method: &str, room_id: u64) -> anyhow::Result<Value> {
+    let result = contract
+        .view(method)
+        .args_json(json!({ "room_id": room_id }))
+        .await?
+        .json::<Value>()?;
+    Ok(result["Ok"].clone())
+}
+
+/// Play the shuffle of a hand in a new room with `scheme`, and return the gas burnt by
+/// the shuffle of each player.
+async fn measure(
+    worker: &Worker<Sandbox>,
+    contract: &Contract,
+    scheme: &str,
+) -> anyhow::Result<Vec<Gas>> {
+    let room_id = contract
+        .call("new_room")
+        .args_json(json!({ "name": scheme, "config": { "scheme": scheme } }))
+        .gas(MAX_GAS)
+        .transact()
+        .await?
+        .json::<Value>()?["Ok"]
+        .as_u64()
+        .unwrap();
+
+    let mut players: Vec<(Account, Value)> = vec![];
+    for _ in 0..NUM_PLAYERS {
+        let account = worker.dev_create_account().await?;
+        let keys = prove(&["key", scheme], None)?;
+        let outcome = account
+            .call(contract.id(), "enter")
+            .args_json(json!({
+                "room_id": room_id,
+                "public_key": { scheme: keys["public_key"] },
+            }))
+            .deposit(NearToken::from_millinear(100))
+            .gas(MAX_GAS)
+            .transact()
+            .await?;
+        anyhow::ensure!(outcome.json::<Value>()?.get("Ok").is_some(), "enter failed");
+        players.push((account, keys["secret_key"].clone()));
+    }
+    for (account, _) in players.iter() {
+        account
+            .call(contract.id(), "ready")
+            .args_json(json!({ "room_id": room_id }))
+            .gas(MAX_GAS)
+            .transact()
+            .await?
+            .into_result()?;
+    }
+    // Rooms that start automatically are already shuffling, and `start` returns an error
+    // that is ignored.
+    players[0]
+        .0
+        .call(contract.id(), "start")
+        .args_json(json!({ "room_id": room_id }))
+        .gas(MAX_GAS)
+        .transact()
+        .await?
+        .into_result()?;
+
+    let deck = view(contract, "deck_state", room_id).await?;
+    let public_keys = deck[scheme]["public_keys"].clone();
+    let mut gas_burnt = vec![];
+    for (account, secret_key) in players.iter() {
+        let cards = view(contract, "get_partial_shuffle", room_id).await?[scheme].clone();
+        let shuffle = prove(
+            &["shuffle"],
+            Some(&json!({
+                "scheme": scheme,
+                "secret_key": secret_key,
+                "cards": cards,
+                "public_keys": public_keys,
+            })),
+        )?;
+        let outcome = account
+            .call(contract.id(), "submit_shuffled")
+            .args_json(json!({
+                "room_id": room_id,
+                "new_cards": { scheme: shuffle["new_cards"] },
+                "proof": { scheme: shuffle["proof"] },
+            }))
+            .gas(MAX_GAS)
+            .transact()
+            .await?;
+        anyhow::ensure!(
+            outcome.json::<Value>()?.get("Ok").is_some(),
+            "shuffle rejected: {:?}",
+            outcome
+        );
+        gas_burnt.push(outcome.total_gas_burnt);
+    }
+    Ok(gas_burnt)
+}
+
+#[tokio::test]
+async fn shuffle_gas() -> anyhow::Result<()> {
+    let wasm = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/../res/poker.wasm"))?;
+    let worker = near_workspaces::sandbox().await?;
+    let contract = worker.dev_deploy(&wasm).await?;
+
+    for scheme in ["Sra", "ElGamal"].iter() {
+        let gas_burnt = measure(&worker, &contract, scheme).await?;
+        for (player_id, gas) in gas_burnt.iter().enumerate() {
+            println!(
+                "{} shuffle of player {}: {} Tgas",
+                scheme,
+                player_id,
+                gas.as_tgas()
+            );
+            assert!(*gas < MAX_GAS);
+        }
+    }
+    Ok(())
+}
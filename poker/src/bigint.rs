@@ -0,0 +1,592 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// Unsigned integer of 256 bits. Limbs are stored from the least significant one.
+/// Serialized as a decimal string in JSON.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct U256(pub [u64; 4]);
+
+#[derive(Debug)]
+pub enum ParseU256Error {
+    Empty,
+    InvalidDigit,
+    Overflow,
+}
+
+impl U256 {
+    pub const ZERO: U256 = U256([0, 0, 0, 0]);
+    pub const ONE: U256 = U256([1, 0, 0, 0]);
+
+    pub fn is_zero(&self) -> bool {
+        *self == Self::ZERO
+    }
+
+    /// Number of bits after the most significant bit set.
+    pub fn bits(&self) -> usize {
+        (0..4)
+            .rev()
+            .find(|&i| self.0[i] != 0)
+            .map_or(0, |i| 64 * i + 64 - self.0[i].leading_zeros() as usize)
+    }
+
+    pub fn bit(&self, index: usize) -> bool {
+        (self.0[index / 64] >> (index % 64)) & 1 == 1
+    }
+
+    /// Value of the `len` bits starting at bit `start`. Bits after the last one are 0.
+    fn bits_at(&self, start: usize, len: usize) -> usize {
+        (start..(start + len).min(256))
+            .rev()
+            .fold(0, |value, index| (value << 1) | self.bit(index) as usize)
+    }
+
+    pub fn from_le_bytes(bytes: &[u8; 32]) -> Self {
+        let mut limbs = [0u64; 4];
+        for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks(8)) {
+            let mut chunk_bytes = [0u8; 8];
+            chunk_bytes.copy_from_slice(chunk);
+            *limb = u64::from_le_bytes(chunk_bytes);
+        }
+        U256(limbs)
+    }
+
+    pub fn to_le_bytes(self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (chunk, limb) in bytes.chunks_mut(8).zip(self.0.iter()) {
+            chunk.copy_from_slice(&limb.to_le_bytes());
+        }
+        bytes
+    }
+
+    fn shr1(&self) -> U256 {
+        let mut result = [0; 4];
+        for (i, limb) in result.iter_mut().enumerate() {
+            *limb = (self.0[i] >> 1) | self.0.get(i + 1).map_or(0, |next| next << 63);
+        }
+        U256(result)
+    }
+
+    pub fn overflowing_add(&self, other: &U256) -> (U256, bool) {
+        let mut result = [0; 4];
+        let mut carry = false;
+        for (i, limb) in result.iter_mut().enumerate() {
+            let (sum, overflow_a) = self.0[i].overflowing_add(other.0[i]);
+            let (sum, overflow_b) = sum.overflowing_add(carry as u64);
+            *limb = sum;
+            carry = overflow_a || overflow_b;
+        }
+        (U256(result), carry)
+    }
+
+    pub fn overflowing_sub(&self, other: &U256) -> (U256, bool) {
+        let mut result = [0; 4];
+        let mut borrow = false;
+        for (i, limb) in result.iter_mut().enumerate() {
+            let (diff, overflow_a) = self.0[i].overflowing_sub(other.0[i]);
+            let (diff, overflow_b) = diff.overflowing_sub(borrow as u64);
+            *limb = diff;
+            borrow = overflow_a || overflow_b;
+        }
+        (U256(result), borrow)
+    }
+
+    /// Multiply by `factor` and add `term`. None on overflow.
+    fn checked_mul_add(&self, factor: u64, term: u64) -> Option<U256> {
+        let mut result = [0; 4];
+        let mut carry = term as u128;
+        for (i, limb) in result.iter_mut().enumerate() {
+            let value = self.0[i] as u128 * factor as u128 + carry;
+            *limb = value as u64;
+            carry = value >> 64;
+        }
+        if carry == 0 {
+            Some(U256(result))
+        } else {
+            None
+        }
+    }
+
+    /// Quotient and remainder of the division by `divisor`.
+    fn div_rem(&self, divisor: u64) -> (U256, u64) {
+        let mut result = [0; 4];
+        let mut remainder = 0u128;
+        for i in (0..4).rev() {
+            let value = (remainder << 64) | self.0[i] as u128;
+            result[i] = (value / divisor as u128) as u64;
+            remainder = value % divisor as u128;
+        }
+        (U256(result), remainder as u64)
+    }
+}
+
+impl From<u64> for U256 {
+    fn from(value: u64) -> Self {
+        U256([value, 0, 0, 0])
+    }
+}
+
+impl From<u128> for U256 {
+    fn from(value: u128) -> Self {
+        U256([value as u64, (value >> 64) as u64, 0, 0])
+    }
+}
+
+impl Ord for U256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+impl PartialOrd for U256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl FromStr for U256 {
+    type Err = ParseU256Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value.is_empty() {
+            return Err(ParseU256Error::Empty);
+        }
+
+        value.chars().try_fold(U256::ZERO, |result, digit| {
+            let digit = digit.to_digit(10).ok_or(ParseU256Error::InvalidDigit)?;
+            result
+                .checked_mul_add(10, digit as u64)
+                .ok_or(ParseU256Error::Overflow)
+        })
+    }
+}
+
+impl fmt::Display for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut digits = vec![];
+        let mut value = *self;
+        loop {
+            let (quotient, digit) = value.div_rem(10);
+            digits.push(std::char::from_digit(digit as u32, 10).unwrap());
+            value = quotient;
+            if value.is_zero() {
+                break;
+            }
+        }
+        f.write_str(&digits.iter().rev().collect::<String>())
+    }
+}
+
+impl fmt::Debug for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl Serialize for U256 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for U256 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <String as Deserialize>::deserialize(deserializer)?
+            .parse()
+            .map_err(|err| de::Error::custom(format!("Invalid U256: {:?}", err)))
+    }
+}
+
+/// Odd modulus with the constants required for Montgomery multiplication.
+pub struct Modulus {
+    pub value: U256,
+    /// `-value^-1` modulo `2^64`.
+    pub inv: u64,
+    /// `2^512` modulo `value`.
+    pub r2: U256,
+}
+
+impl Modulus {
+    /// `a * b * 2^-256` modulo the modulus. Both values should be lower than the modulus.
+    fn montgomery_mul(&self, a: &U256, b: &U256) -> U256 {
+        let n = &self.value.0;
+        let mut t = [0u64; 6];
+
+        for i in 0..4 {
+            let mut carry = 0u64;
+            for (limb, &a_limb) in t.iter_mut().zip(a.0.iter()) {
+                let value = *limb as u128 + a_limb as u128 * b.0[i] as u128 + carry as u128;
+                *limb = value as u64;
+                carry = (value >> 64) as u64;
+            }
+            let value = t[4] as u128 + carry as u128;
+            t[4] = value as u64;
+            t[5] = (value >> 64) as u64;
+
+            let m = t[0].wrapping_mul(self.inv);
+            let value = t[0] as u128 + m as u128 * n[0] as u128;
+            let mut carry = (value >> 64) as u64;
+            for j in 1..4 {
+                let value = t[j] as u128 + m as u128 * n[j] as u128 + carry as u128;
+                t[j - 1] = value as u64;
+                carry = (value >> 64) as u64;
+            }
+            let value = t[4] as u128 + carry as u128;
+            t[3] = value as u64;
+            t[4] = t[5] + (value >> 64) as u64;
+        }
+
+        let result = U256([t[0], t[1], t[2], t[3]]);
+        if t[4] != 0 || result >= self.value {
+            result.overflowing_sub(&self.value).0
+        } else {
+            result
+        }
+    }
+
+    /// Reduce a value lower than twice the modulus.
+    fn reduce(&self, value: &U256) -> U256 {
+        if *value >= self.value {
+            value.overflowing_sub(&self.value).0
+        } else {
+            *value
+        }
+    }
+
+    pub fn mul(&self, a: &U256, b: &U256) -> U256 {
+        let product = self.montgomery_mul(&self.reduce(a), &self.reduce(b));
+        self.montgomery_mul(&product, &self.r2)
+    }
+
+    /// `a + b` modulo the modulus. Both values should be lower than the modulus.
+    pub fn add(&self, a: &U256, b: &U256) -> U256 {
+        let (sum, carry) = a.overflowing_add(b);
+        if carry || sum >= self.value {
+            sum.overflowing_sub(&self.value).0
+        } else {
+            sum
+        }
+    }
+
+    /// `a - b` modulo the modulus. Both values should be lower than the modulus.
+    pub fn sub(&self, a: &U256, b: &U256) -> U256 {
+        let (difference, borrow) = a.overflowing_sub(b);
+        if borrow {
+            difference.overflowing_add(&self.value).0
+        } else {
+            difference
+        }
+    }
+
+    pub fn pow(&self, base: &U256, exponent: &U256) -> U256 {
+        let base = self.montgomery_mul(&self.reduce(base), &self.r2);
+        let mut result = self.montgomery_mul(&U256::ONE, &self.r2);
+        for i in (0..exponent.bits()).rev() {
+            result = self.montgomery_mul(&result, &result);
+            if exponent.bit(i) {
+                result = self.montgomery_mul(&result, &base);
+            }
+        }
+        self.montgomery_mul(&result, &U256::ONE)
+    }
+
+    /// Product of `base^exponent` for every term. Computed with the bucket method, where
+    /// each window of `MULTI_POW_WINDOW` bits of the exponents takes one multiplication
+    /// per term instead of a full exponentiation per term.
+    pub fn multi_pow(&self, terms: &[(U256, U256)]) -> U256 {
+        let bases = terms
+            .iter()
+            .map(|(base, _)| self.montgomery_mul(&self.reduce(base), &self.r2))
+            .collect::<Vec<_>>();
+        let bits = terms
+            .iter()
+            .map(|(_, exponent)| exponent.bits())
+            .max()
+            .unwrap_or(0);
+
+        let mut result = self.montgomery_mul(&U256::ONE, &self.r2);
+        for start in (0..bits).step_by(MULTI_POW_WINDOW).rev() {
+            for _ in 0..MULTI_POW_WINDOW {
+                result = self.montgomery_mul(&result, &result);
+            }
+
+            // Bucket `d - 1` holds the product of the bases with digit `d` in this window.
+            let mut buckets = vec![None; (1 << MULTI_POW_WINDOW) - 1];
+            for (base, (_, exponent)) in bases.iter().zip(terms.iter()) {
+                let digit = exponent.bits_at(start, MULTI_POW_WINDOW);
+                if digit > 0 {
+                    let bucket = &mut buckets[digit - 1];
+                    *bucket = Some(match bucket {
+                        Some(product) => self.montgomery_mul(product, base),
+                        None => *base,
+                    });
+                }
+            }
+
+            // Each bucket is multiplied into the running product once per digit up to its own.
+            let mut running = None;
+            for bucket in buckets.iter().rev() {
+                if let Some(bucket) = bucket {
+                    running = Some(
+                        running.map_or(*bucket, |running| self.montgomery_mul(&running, bucket)),
+                    );
+                }
+                if let Some(running) = &running {
+                    result = self.montgomery_mul(&result, running);
+                }
+            }
+        }
+        self.montgomery_mul(&result, &U256::ONE)
+    }
+
+    /// Whether `value` is a nonzero square modulo the modulus, which should be prime.
+    /// Computed as the Jacobi symbol with the binary algorithm, which is much cheaper than
+    /// an exponentiation.
+    pub fn is_square(&self, value: &U256) -> bool {
+        let mut a = self.reduce(value);
+        let mut n = self.value;
+        let mut positive = true;
+        loop {
+            if a.is_zero() {
+                return n == U256::ONE && positive;
+            }
+            while !a.bit(0) {
+                a = a.shr1();
+                if n.0[0] & 7 == 3 || n.0[0] & 7 == 5 {
+                    positive = !positive;
+                }
+            }
+            if a < n {
+                std::mem::swap(&mut a, &mut n);
+                if a.0[0] & 3 == 3 && n.0[0] & 3 == 3 {
+                    positive = !positive;
+                }
+            }
+            a = a.overflowing_sub(&n).0;
+        }
+    }
+}
+
+/// Bits of the exponents handled at once by `multi_pow`. Sized for the few hundred terms
+/// of a shuffle proof.
+const MULTI_POW_WINDOW: usize = 6;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sra::MODULUS;
+
+    /// Mersenne prime `2^61 - 1`, a modulus narrower than the limbs.
+    const SMALL_MODULUS: Modulus = Modulus {
+        value: U256([0x1fff_ffff_ffff_ffff, 0, 0, 0]),
+        inv: 0x2000_0000_0000_0001,
+        r2: U256([0x100_0000, 0, 0, 0]),
+    };
+
+    // Expected values below are computed with python `a * b % MOD` and `pow(a, b, MOD)`.
+    const MAX: &str =
+        "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+    const P: &str =
+        "115792089237316195423570985008687907853269984665640564039457584007913129603823";
+    const A: &str = "70773806083378955857461738073380992800765470174266196554743400306770159545300";
+    const B: &str = "75384219386287612980094041215751813587813928916684004327104585217274972913165";
+    const E: &str = "20613134839093778782694883372478415219033443904884189023430750931575869393807";
+
+    fn u(value: &str) -> U256 {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_and_display() {
+        assert_eq!(u("0"), U256::ZERO);
+        assert_eq!(u("007"), U256::from(7u64));
+        assert_eq!(u(MAX), U256([u64::MAX; 4]));
+        assert_eq!(u(P), MODULUS.value);
+        assert_eq!(
+            u(A),
+            U256([
+                0xb39e_201d_6207_2bd4,
+                0x7252_43ab_f8cb_f43b,
+                0x63a4_cd11_d804_6b21,
+                0x9c78_8e00_51fd_e96d,
+            ])
+        );
+
+        for value in ["0", "1", "18446744073709551616", MAX, P, A].iter() {
+            assert_eq!(u(value).to_string(), *value);
+        }
+
+        assert!(matches!("".parse::<U256>(), Err(ParseU256Error::Empty)));
+        assert!(matches!(
+            "12a".parse::<U256>(),
+            Err(ParseU256Error::InvalidDigit)
+        ));
+        assert!(matches!(
+            "-1".parse::<U256>(),
+            Err(ParseU256Error::InvalidDigit)
+        ));
+        assert!(matches!(
+            "115792089237316195423570985008687907853269984665640564039457584007913129639936"
+                .parse::<U256>(),
+            Err(ParseU256Error::Overflow)
+        ));
+    }
+
+    #[test]
+    fn montgomery_mul() {
+        let p_minus_one = MODULUS.value.overflowing_sub(&U256::ONE).0;
+        let p_plus_five = MODULUS.value.overflowing_add(&U256::from(5u64)).0;
+        let cases = [
+            (
+                u(A),
+                u(B),
+                u("16488553211650906801760329512431025582750706858680363596034420039458100794809"),
+            ),
+            (p_minus_one, p_minus_one, U256::ONE),
+            (
+                p_minus_one,
+                U256::from(2u64),
+                MODULUS.value.overflowing_sub(&U256::from(2u64)).0,
+            ),
+            (p_plus_five, U256::from(3u64), U256::from(15u64)),
+            (u(MAX), u(MAX), U256::from(1_304_076_544u64)),
+            (u(A), U256::ZERO, U256::ZERO),
+            (u(A), U256::ONE, u(A)),
+        ];
+
+        for (a, b, expected) in cases.iter() {
+            assert_eq!(MODULUS.mul(a, b), *expected, "{} * {}", a, b);
+            assert_eq!(MODULUS.mul(b, a), *expected, "{} * {}", b, a);
+        }
+
+        assert_eq!(
+            SMALL_MODULUS.mul(
+                &U256::from(123_456_789_123_456_789u64),
+                &U256::from(987_654_321_987_654_321u64)
+            ),
+            U256::from(587_437_849_037_674_763u64)
+        );
+    }
+
+    #[test]
+    fn montgomery_pow() {
+        let p_minus_one = MODULUS.value.overflowing_sub(&U256::ONE).0;
+        let p_minus_two = MODULUS.value.overflowing_sub(&U256::from(2u64)).0;
+        let cases = [
+            (
+                u(A),
+                u(E),
+                u("34383249033741602159782054799928831071554474318143280634526250557989578939627"),
+            ),
+            (
+                u(A),
+                u(B),
+                u("70384120219479006026863046187276346914642171328436783960009742778036281174657"),
+            ),
+            (
+                u(MAX),
+                u(MAX),
+                u("16646881071922184702524138989080010245292565524800054468386668914911356970922"),
+            ),
+            (
+                u(A),
+                p_minus_two,
+                u("6818679124233272921658470837272439751721154046302378155195950807304982859916"),
+            ),
+            (U256::from(2u64), p_minus_one, U256::ONE),
+            (p_minus_one, crate::sra::ORDER, p_minus_one),
+            (u(A), U256::ZERO, U256::ONE),
+            (U256::ZERO, u(E), U256::ZERO),
+            (u(A), U256::ONE, u(A)),
+        ];
+
+        for (base, exponent, expected) in cases.iter() {
+            assert_eq!(
+                MODULUS.pow(base, exponent),
+                *expected,
+                "{} ^ {}",
+                base,
+                exponent
+            );
+        }
+
+        assert_eq!(
+            SMALL_MODULUS.pow(
+                &U256::from(123_456_789_123_456_789u64),
+                &U256::from(987_654_321_987_654_321u64)
+            ),
+            U256::from(295_784_571_051_488_292u64)
+        );
+        assert_eq!(
+            SMALL_MODULUS.pow(&U256::from(3u64), &U256::from(0x1fff_ffff_ffff_fffeu64)),
+            U256::ONE
+        );
+    }
+
+    #[test]
+    fn le_bytes() {
+        let bytes = u(A).to_le_bytes();
+        assert_eq!(bytes[0], 0xd4);
+        assert_eq!(bytes[31], 0x9c);
+        assert_eq!(U256::from_le_bytes(&bytes), u(A));
+        assert_eq!(U256::from_le_bytes(&[0xff; 32]), u(MAX));
+        assert_eq!(U256::ONE.to_le_bytes()[0], 1);
+    }
+
+    #[test]
+    fn modular_add_and_sub() {
+        let p_minus_one = MODULUS.value.overflowing_sub(&U256::ONE).0;
+        assert_eq!(
+            MODULUS.add(&u(A), &u(B)),
+            u("30365936232350373413984794280444898535309414425309636842390401516132002854642")
+        );
+        assert_eq!(
+            MODULUS.sub(&u(B), &u(A)),
+            u("4610413302908657122632303142370820787048458742417807772361184910504813367865")
+        );
+        assert_eq!(
+            MODULUS.sub(&u(A), &u(B)),
+            u("111181675934407538300938681866317087066221525923222756267096399097408316235958")
+        );
+        assert_eq!(MODULUS.add(&p_minus_one, &U256::ONE), U256::ZERO);
+        assert_eq!(MODULUS.sub(&U256::ZERO, &U256::ONE), p_minus_one);
+    }
+
+    #[test]
+    fn multi_pow() {
+        assert_eq!(MODULUS.multi_pow(&[]), U256::ONE);
+
+        let bases = [u(A), u(B), u(MAX), U256::from(2u64), U256::ZERO];
+        let exponents = [u(E), u(B), U256::ZERO, U256::ONE, u(A)];
+        let mut terms = Vec::new();
+        let mut expected = U256::ONE;
+        for (base, exponent) in bases.iter().zip(exponents.iter()) {
+            terms.push((*base, *exponent));
+            expected = MODULUS.mul(&expected, &MODULUS.pow(base, exponent));
+            assert_eq!(MODULUS.multi_pow(&terms), expected);
+        }
+
+        let terms: Vec<_> = (1..40u64)
+            .map(|i| (U256::from(i), MODULUS.pow(&u(A), &U256::from(i))))
+            .collect();
+        let expected = terms.iter().fold(U256::ONE, |product, (base, exponent)| {
+            MODULUS.mul(&product, &MODULUS.pow(base, exponent))
+        });
+        assert_eq!(MODULUS.multi_pow(&terms), expected);
+    }
+
+    #[test]
+    fn quadratic_residues() {
+        let p_minus_one = MODULUS.value.overflowing_sub(&U256::ONE).0;
+        assert!(MODULUS.is_square(&U256::ONE));
+        assert!(MODULUS.is_square(&u(B)));
+        assert!(!MODULUS.is_square(&u(A)));
+        assert!(!MODULUS.is_square(&p_minus_one));
+        assert!(!MODULUS.is_square(&U256::ZERO));
+        for value in [u(A), u(E), U256::from(3u64)].iter() {
+            assert!(MODULUS.is_square(&MODULUS.mul(value, value)));
+        }
+    }
+}
@@ -0,0 +1,293 @@
+use crate::hand::{BOARD_SIZE, DECK_SIZE};
+use crate::poker::BettingStructure;
+use crate::scheme::CardScheme;
+use crate::types::{balance_format, AccountId, Balance};
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_bindgen::env;
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of players that can sit on a table. Each player needs two cards
+/// and there should be enough cards left for the board.
+pub const MAX_PLAYERS: u64 = (DECK_SIZE - BOARD_SIZE) / 2;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Debug)]
+pub enum ConfigError {
+    /// There should be at least two players and at most `MAX_PLAYERS`.
+    InvalidNumberOfPlayers,
+    /// Minimum buy-in should be enough to pay the first big blind, and not higher than
+    /// maximum buy-in.
+    InvalidBuyIn,
+    /// Chips should have a positive value, and the value of all the tokens a full table
+    /// can hold should be representable as a balance.
+    InvalidChipValue,
+    /// At least one blind level is required.
+    EmptyBlindSchedule,
+    /// Big blind should be positive and not lower than small blind.
+    InvalidBlindLevel,
+    /// Blind levels should last a positive number of hands or nanoseconds.
+    InvalidLevelDuration,
+    /// Straddles are not allowed with fixed limit betting.
+    InvalidStraddle,
+    /// Players should have a positive number of blocks or nanoseconds to act.
+    InvalidActionTimeout,
+    /// Time bank should be topped up after a positive number of hands.
+    InvalidTimeBank,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub enum Variant {
+    TexasHoldem,
+}
+
+/// Currency used to buy tokens in a room.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub enum Currency {
+    /// Tokens are bought attaching NEAR to `enter`.
+    Near,
+    /// Tokens are bought transferring a NEP-141 fungible token with `ft_transfer_call`.
+    FungibleToken {
+        /// Contract of the fungible token.
+        account_id: AccountId,
+        /// Number of decimals of the fungible token. Only used to display amounts.
+        decimals: u8,
+    },
+}
+
+impl Currency {
+    /// Contract of the fungible token. None for NEAR.
+    pub fn token_account_id(&self) -> Option<&AccountId> {
+        match self {
+            Currency::Near => None,
+            Currency::FungibleToken { account_id, .. } => Some(account_id),
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub enum AnteType {
+    /// Every player puts the ante of the current level.
+    Standard,
+    /// The big blind puts the ante of the current level on behalf of the whole table.
+    BigBlind,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub enum LevelDuration {
+    /// Level is over after this number of hands.
+    Hands(u64),
+    /// Level is over after this number of nanoseconds, measured using block timestamp.
+    /// The hand being played when the time is over is finished using current level.
+    Time(u64),
+}
+
+/// Time a player has to make an action before the game can be unblocked.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub enum Timeout {
+    /// Number of blocks, measured using block index.
+    Blocks(u64),
+    /// Number of nanoseconds, measured using block timestamp.
+    Time(u64),
+}
+
+impl Timeout {
+    /// Current block index or block timestamp, depending on how the timeout is measured.
+    pub fn now(&self) -> u64 {
+        match self {
+            Timeout::Blocks(_) => env::block_index(),
+            Timeout::Time(_) => env::block_timestamp(),
+        }
+    }
+
+    /// Deadline for an action starting now.
+    pub fn deadline(&self) -> u64 {
+        match *self {
+            Timeout::Blocks(blocks) => self.now().saturating_add(blocks),
+            Timeout::Time(time) => self.now().saturating_add(time),
+        }
+    }
+}
+
+/// Extra time each player can use when its action clock runs out. Measured in the same
+/// unit as the action timeout.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct TimeBank {
+    /// Time bank of each player when entering the room.
+    pub initial: u64,
+    /// Time added to the time bank of each player every `every_hands` hands. The time bank
+    /// is never topped up above `initial`.
+    pub top_up: u64,
+    pub every_hands: u64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct BlindLevel {
+    pub small_blind: u64,
+    pub big_blind: u64,
+    /// Tokens put in the pot before the cards are dealt. They don't count as stake to be
+    /// called on the first betting round.
+    pub ante: u64,
+    pub duration: LevelDuration,
+}
+
+/// Parameters of a table. They are fixed when the room is created.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct TableConfig {
+    /// Minimum number of players required to start the game.
+    pub min_players: u64,
+    /// Maximum number of players allowed in the room.
+    pub max_players: u64,
+    /// Minimum number of tokens a player can buy when entering the room.
+    pub min_buy_in: u64,
+    /// Maximum number of tokens a player can buy when entering the room.
+    pub max_buy_in: u64,
+    /// Currency used to buy tokens.
+    pub currency: Currency,
+    /// Price of each token in the smallest unit of the currency (yoctoNEAR for NEAR).
+    #[serde(with = "balance_format")]
+    pub chip_value: Balance,
+    /// Blinds used on each level. After the last level the blinds don't change anymore.
+    pub blind_levels: Vec<BlindLevel>,
+    /// Who puts the ante on each hand.
+    pub ante_type: AnteType,
+    /// Player next to the big blind can choose on each hand to put twice the big blind
+    /// before the cards are dealt, and act last on the first betting round. Only with
+    /// three or more players.
+    pub straddle: bool,
+    pub betting_structure: BettingStructure,
+    pub variant: Variant,
+    /// Scheme used to encrypt and shuffle the cards.
+    pub scheme: CardScheme,
+    /// Time each player has to make an action. After that, anyone can claim the timeout.
+    pub action_timeout: Timeout,
+    /// Extra time players can use to act. None if there is no time bank.
+    pub time_bank: Option<TimeBank>,
+    /// Start the next hand as soon as every player is ready.
+    pub auto_start: bool,
+}
+
+impl Default for TableConfig {
+    fn default() -> Self {
+        Self {
+            min_players: 2,
+            max_players: 9,
+            min_buy_in: 100,
+            max_buy_in: 1000,
+            currency: Currency::Near,
+            // 1 NEAR buys 1000 tokens.
+            chip_value: 1_000_000_000_000_000_000_000,
+            blind_levels: vec![3, 5, 10, 15, 25, 50, 100, 200]
+                .into_iter()
+                .map(|small_blind| BlindLevel {
+                    small_blind,
+                    big_blind: 2 * small_blind,
+                    ante: 0,
+                    duration: LevelDuration::Hands(10),
+                })
+                .collect(),
+            ante_type: AnteType::Standard,
+            straddle: false,
+            betting_structure: BettingStructure::default(),
+            variant: Variant::TexasHoldem,
+            scheme: CardScheme::ElGamal,
+            // Five minutes.
+            action_timeout: Timeout::Time(300_000_000_000),
+            time_bank: None,
+            auto_start: false,
+        }
+    }
+}
+
+impl TableConfig {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.min_players < 2
+            || self.max_players < self.min_players
+            || self.max_players > MAX_PLAYERS
+        {
+            return Err(ConfigError::InvalidNumberOfPlayers);
+        }
+
+        let first_level = self
+            .blind_levels
+            .first()
+            .ok_or(ConfigError::EmptyBlindSchedule)?;
+
+        if self
+            .blind_levels
+            .iter()
+            .any(|level| level.big_blind == 0 || level.small_blind > level.big_blind)
+        {
+            return Err(ConfigError::InvalidBlindLevel);
+        }
+
+        if self.blind_levels.iter().any(|level| match level.duration {
+            LevelDuration::Hands(hands) => hands == 0,
+            LevelDuration::Time(time) => time == 0,
+        }) {
+            return Err(ConfigError::InvalidLevelDuration);
+        }
+
+        if self.straddle && self.betting_structure == BettingStructure::FixedLimit {
+            return Err(ConfigError::InvalidStraddle);
+        }
+
+        if let Timeout::Blocks(0) | Timeout::Time(0) = self.action_timeout {
+            return Err(ConfigError::InvalidActionTimeout);
+        }
+
+        if let Some(TimeBank { every_hands: 0, .. }) = self.time_bank {
+            return Err(ConfigError::InvalidTimeBank);
+        }
+
+        if self.min_buy_in < first_level.big_blind || self.max_buy_in < self.min_buy_in {
+            return Err(ConfigError::InvalidBuyIn);
+        }
+
+        // All the tokens at the table can end up with a single player.
+        let max_table_tokens = self.max_buy_in.checked_mul(self.max_players);
+        if self.chip_value == 0
+            || max_table_tokens
+                .and_then(|tokens| self.chip_value.checked_mul(tokens.into()))
+                .is_none()
+        {
+            return Err(ConfigError::InvalidChipValue);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chip_value_of_full_table() {
+        let config = TableConfig {
+            max_players: 9,
+            max_buy_in: 1000,
+            chip_value: Balance::MAX / 9000,
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+
+        let config = TableConfig {
+            chip_value: Balance::MAX / 1000,
+            ..config
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidChipValue)
+        ));
+
+        let config = TableConfig {
+            chip_value: 1,
+            max_buy_in: u64::MAX / 2,
+            ..config
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidChipValue)
+        ));
+    }
+}
@@ -1,5 +1,9 @@
+use crate::elgamal::ElGamal;
+use crate::scheme::{
+    CardScheme, Cards, PublicKey, RevealPart, RevealProof, Scheme, SecretKey, ShuffleProof,
+};
+use crate::sra::Sra;
 use crate::types::AccountId;
-use crate::types::CryptoHash;
 use crate::types::{CardId, PlayerId};
 use borsh::{BorshDeserialize, BorshSerialize};
 use near_bindgen::env;
@@ -21,10 +25,27 @@ pub enum DeckError {
     NotRevealing,
     /// Tried to reveal part but it's not player turn to reveal
     PlayerCantReveal,
+    /// Shuffled cards are not a permutation of the previous cards encrypted with a
+    /// single key, or the proof is malformed.
+    InvalidShuffleProof,
+    /// Public key can't be used to encrypt cards with the scheme of the deck.
+    InvalidPublicKey,
+    /// Revealed part was not computed from the current progress with the key of the
+    /// player, or the proof is malformed.
+    InvalidRevealProof,
+    /// Submitted keys, cards or proofs belong to a scheme different from the one used by
+    /// the deck.
+    InvalidScheme,
+    /// Tried to reveal a secret key, but no hand was played yet.
+    NothingToAudit,
+    /// Player already revealed its secret key for the last hand.
+    SecretKeyAlreadyRevealed,
+    /// Revealed secret key doesn't match the public key used by the player in the hand.
+    InvalidSecretKey,
 }
 
 #[derive(PartialEq, Eq, Clone, BorshDeserialize, BorshSerialize, Serialize, Debug)]
-pub enum DeckStatus {
+pub enum DeckStatus<Card> {
     Initiating,
     Shuffling(PlayerId),
     Running,
@@ -39,34 +60,134 @@ pub enum DeckStatus {
         // It can be the receiver if it should fetch its part.
         turn: PlayerId,
         // Partially decrypted card.
-        progress: CryptoHash,
+        progress: Card,
     },
     Closed,
 }
 
-impl Default for DeckStatus {
+impl<Card> Default for DeckStatus<Card> {
     fn default() -> Self {
         Self::Initiating
     }
 }
 
-#[derive(BorshDeserialize, BorshSerialize, Default, Serialize, Clone)]
-pub struct Deck {
-    status: DeckStatus,
+/// Submission of a player during a hand, kept to replay it once the hand is over.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
+pub enum Step<S: Scheme> {
+    /// Cards submitted with `submit_shuffled`.
+    Shuffle {
+        player_id: PlayerId,
+        cards: Vec<S::Card>,
+    },
+    /// Part submitted with `submit_reveal_part` to remove the encryption of the player
+    /// from `progress`.
+    Reveal {
+        player_id: PlayerId,
+        progress: S::Card,
+        part: S::RevealPart,
+    },
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone, PartialEq, Debug)]
+pub enum AuditResult {
+    /// Every submission matches the secret keys of the players.
+    Valid,
+    /// First submission that doesn't match the secret keys. `step` is its position in
+    /// the steps of the hand.
+    Cheated {
+        step: u64,
+        player_id: PlayerId,
+        account_id: AccountId,
+    },
+}
+
+/// Hand already played. As players reveal the secret keys they used, each step that can
+/// be checked with the revealed keys is replayed to find out whether some player cheated.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
+#[serde(bound = "")]
+pub struct Audit<S: Scheme> {
+    /// Players of the hand. Player ids in the steps are positions in this list.
+    players: Vec<AccountId>,
+    public_keys: Vec<S::PublicKey>,
+    num_cards: u64,
+    steps: Vec<Step<S>>,
+    /// Secret key revealed by each player. None if not revealed yet.
+    secret_keys: Vec<Option<S::SecretKey>>,
+    /// None until a cheater is found, or every player reveals its secret key.
+    result: Option<AuditResult>,
+}
+
+impl<S: Scheme> Audit<S> {
+    /// Replay the steps of the hand that can be checked with the secret keys revealed so
+    /// far. None if none of them is inconsistent but some secret key is not revealed yet.
+    fn replay(&self) -> Option<AuditResult> {
+        let initial_cards = S::initial_cards(self.num_cards);
+        let mut cards = &initial_cards[..];
+
+        for (step, submission) in self.steps.iter().enumerate() {
+            let (player_id, valid) = match submission {
+                Step::Shuffle {
+                    player_id,
+                    cards: new_cards,
+                } => {
+                    let valid = S::replay_shuffle(&self.secret_keys, *player_id, cards, new_cards);
+                    cards = new_cards;
+                    (*player_id, valid)
+                }
+                Step::Reveal {
+                    player_id,
+                    progress,
+                    part,
+                } => (
+                    *player_id,
+                    self.secret_keys[*player_id as usize]
+                        .as_ref()
+                        .map(|secret_key| S::replay_reveal(secret_key, progress, part)),
+                ),
+            };
+
+            if valid == Some(false) {
+                return Some(AuditResult::Cheated {
+                    step: step as u64,
+                    player_id,
+                    account_id: self.players[player_id as usize].clone(),
+                });
+            }
+        }
+
+        if self.secret_keys.iter().all(Option::is_some) {
+            Some(AuditResult::Valid)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
+#[serde(bound = "")]
+pub struct Deck<S: Scheme> {
+    status: DeckStatus<S::Card>,
     players: Vec<AccountId>,
-    cards: Vec<CryptoHash>,
-    pub revealed: Vec<Option<CryptoHash>>,
+    /// Public key of each player, used to check its shuffles and revealed parts.
+    public_keys: Vec<S::PublicKey>,
+    cards: Vec<S::Card>,
+    revealed: Vec<Option<S::Card>>,
+    /// Submissions of the current hand.
+    history: Vec<Step<S>>,
+    /// Audit of the last hand played.
+    audit: Option<Audit<S>>,
 }
 
-impl Deck {
-    // TODO: Add password.
-    // TODO: Add minimum/maximum amount of players.
+impl<S: Scheme> Deck<S> {
     pub fn new(num_cards: u64) -> Self {
         Self {
             status: DeckStatus::Initiating,
             players: vec![],
-            cards: (0..num_cards).map(|num| num.to_string()).collect(),
+            public_keys: vec![],
+            cards: S::initial_cards(num_cards),
             revealed: vec![None; num_cards as usize],
+            history: vec![],
+            audit: None,
         }
     }
 
@@ -79,7 +200,7 @@ impl Deck {
     }
 
     pub fn get_player_id(&self) -> Result<PlayerId, DeckError> {
-        let account_id = env::signer_account_id();
+        let account_id = env::predecessor_account_id();
         self.players
             .iter()
             .position(|player_account_id| player_account_id == &account_id)
@@ -87,13 +208,19 @@ impl Deck {
             .ok_or(DeckError::PlayerNotInGame)
     }
 
-    pub fn enter(&mut self) -> Result<(), DeckError> {
+    pub fn enter(
+        &mut self,
+        account_id: AccountId,
+        public_key: S::PublicKey,
+    ) -> Result<(), DeckError> {
         if self.status == DeckStatus::Initiating {
-            let account_id = env::signer_account_id();
             if self.players.contains(&account_id) {
                 Err(DeckError::PlayerAlreadyInGame)
+            } else if !S::is_valid_public_key(&public_key) {
+                Err(DeckError::InvalidPublicKey)
             } else {
                 self.players.push(account_id);
+                self.public_keys.push(public_key);
                 Ok(())
             }
         } else {
@@ -101,20 +228,37 @@ impl Deck {
         }
     }
 
+    /// Remove `player_id` from the deck. Players after it move one position down.
+    /// Only allowed while no deck is in progress.
+    pub fn remove_player(&mut self, player_id: PlayerId) -> Result<AccountId, DeckError> {
+        match self.status {
+            DeckStatus::Initiating | DeckStatus::Closed => {
+                if player_id >= self.num_players() {
+                    Err(DeckError::InvalidPlayerId)
+                } else {
+                    self.public_keys.remove(player_id as usize);
+                    Ok(self.players.remove(player_id as usize))
+                }
+            }
+            _ => Err(DeckError::DeckInProgress),
+        }
+    }
+
     pub fn start(&mut self) -> Result<(), DeckError> {
         match self.status {
             DeckStatus::Initiating | DeckStatus::Closed => {
                 self.status = DeckStatus::Shuffling(0);
                 let num_cards = self.cards.len();
-                self.cards = (0..num_cards).map(|num| num.to_string()).collect();
+                self.cards = S::initial_cards(num_cards as u64);
                 self.revealed = vec![None; num_cards];
+                self.history.clear();
                 Ok(())
             }
             _ => Err(DeckError::DeckInProgress),
         }
     }
 
-    pub fn get_status(&self) -> DeckStatus {
+    pub fn get_status(&self) -> DeckStatus<S::Card> {
         self.status.clone()
     }
 
@@ -126,7 +270,7 @@ impl Deck {
         }
     }
 
-    pub fn get_revealed_card(&self, card_id: CardId) -> Result<CryptoHash, DeckError> {
+    pub fn get_revealed_card(&self, card_id: CardId) -> Result<S::Card, DeckError> {
         self.revealed
             .get(card_id as usize)
             .ok_or(DeckError::InvalidCardId)?
@@ -134,7 +278,16 @@ impl Deck {
             .ok_or(DeckError::CardNotRevealed)
     }
 
-    pub fn get_partial_shuffle(&self) -> Result<Vec<CryptoHash>, DeckError> {
+    /// Cards revealed to every player, decoded. None for cards not revealed.
+    pub fn revealed_cards(&self) -> Vec<Option<u64>> {
+        let num_cards = self.cards.len() as u64;
+        self.revealed
+            .iter()
+            .map(|card| S::decode_card(card.as_ref()?, num_cards))
+            .collect()
+    }
+
+    pub fn get_partial_shuffle(&self) -> Result<Vec<S::Card>, DeckError> {
         if let DeckStatus::Shuffling(_) = self.status {
             Ok(self.cards.clone())
         } else {
@@ -142,17 +295,124 @@ impl Deck {
         }
     }
 
-    pub fn close(&mut self) {
+    /// Finish the hand. If some player submitted cards, the hand is audited as players
+    /// reveal their secret keys. Return the players that never revealed the key of the
+    /// previous hand, whose audit is replaced.
+    pub fn close(&mut self) -> Vec<AccountId> {
+        let mut withheld = vec![];
+        if !self.history.is_empty() {
+            if let Some(audit) = self.audit.as_ref() {
+                withheld = audit
+                    .players
+                    .iter()
+                    .zip(audit.secret_keys.iter())
+                    .filter(|(_, secret_key)| secret_key.is_none())
+                    .map(|(player, _)| player.clone())
+                    .collect();
+            }
+
+            self.audit = Some(Audit {
+                players: self.players.clone(),
+                public_keys: self.public_keys.clone(),
+                num_cards: self.cards.len() as u64,
+                steps: std::mem::take(&mut self.history),
+                secret_keys: vec![None; self.players.len()],
+                result: None,
+            });
+        }
         self.status = DeckStatus::Closed;
+        withheld
+    }
+
+    /// Whether `account_id`, still seated, should reveal its secret key for the last hand.
+    pub fn is_secret_key_pending(&self, account_id: &AccountId) -> bool {
+        self.audit.as_ref().map_or(false, |audit| {
+            self.players.contains(account_id)
+                && audit
+                    .players
+                    .iter()
+                    .zip(audit.secret_keys.iter())
+                    .any(|(player, secret_key)| player == account_id && secret_key.is_none())
+        })
+    }
+
+    /// Reveal the secret key used in the last hand. Since it can't be used anymore,
+    /// players still seated should register `public_key`, a new key for the next hands.
+    /// Return the result of the audit if this key settled it: the first cheater found with
+    /// the keys revealed so far, or a valid hand once every player revealed its key.
+    pub fn submit_secret_key(
+        &mut self,
+        secret_key: S::SecretKey,
+        public_key: Option<S::PublicKey>,
+    ) -> Result<Option<AuditResult>, DeckError> {
+        match self.status {
+            DeckStatus::Initiating | DeckStatus::Closed => {}
+            _ => return Err(DeckError::DeckInProgress),
+        }
+
+        let account_id = env::predecessor_account_id();
+        let seat = self.players.iter().position(|player| player == &account_id);
+        let audit = self.audit.as_mut().ok_or(DeckError::NothingToAudit)?;
+        let player_id = audit
+            .players
+            .iter()
+            .position(|player| player == &account_id)
+            .ok_or(DeckError::PlayerNotInGame)?;
+
+        if audit.secret_keys[player_id].is_some() {
+            return Err(DeckError::SecretKeyAlreadyRevealed);
+        }
+
+        if !S::is_secret_key(&audit.public_keys[player_id], &secret_key) {
+            return Err(DeckError::InvalidSecretKey);
+        }
+
+        if let Some(seat) = seat {
+            match public_key {
+                Some(public_key)
+                    if S::is_valid_public_key(&public_key)
+                        && !S::is_secret_key(&public_key, &secret_key) =>
+                {
+                    self.public_keys[seat] = public_key;
+                }
+                _ => return Err(DeckError::InvalidPublicKey),
+            }
+        }
+
+        audit.secret_keys[player_id] = Some(secret_key);
+        if audit.result.is_some() {
+            return Ok(None);
+        }
+        audit.result = audit.replay();
+        Ok(audit.result.clone())
     }
 
-    // TODO: Add zk-proof for correct computation of the cards (given previous set of cards and public key)
-    pub fn submit_shuffled(&mut self, new_cards: Vec<CryptoHash>) -> Result<(), DeckError> {
+    /// Replace the cards with `new_cards`, which should be the current cards encrypted
+    /// with the key of the player and shuffled. `proof` shows that it is the case.
+    pub fn submit_shuffled(
+        &mut self,
+        new_cards: Vec<S::Card>,
+        proof: S::ShuffleProof,
+    ) -> Result<(), DeckError> {
         if let DeckStatus::Shuffling(current_player_id) = self.status {
             let player_id = self.get_player_id()?;
             if player_id != current_player_id {
                 Err(DeckError::InvalidTurn)
             } else {
+                if !S::verify_shuffle(
+                    &self.public_keys,
+                    player_id,
+                    &self.cards,
+                    &new_cards,
+                    &proof,
+                ) {
+                    return Err(DeckError::InvalidShuffleProof);
+                }
+
+                self.history.push(Step::Shuffle {
+                    player_id,
+                    cards: new_cards.clone(),
+                });
                 self.cards = new_cards;
 
                 if current_player_id + 1 < self.num_players() {
@@ -217,13 +477,18 @@ impl Deck {
         }
     }
 
-    // TODO: Add zk-proof using previous part and public key
-    pub fn submit_reveal_part(&mut self, card: CryptoHash) -> Result<(), DeckError> {
+    /// Submit `part`, used to remove the encryption of the player from the current
+    /// progress. `proof` shows that it was computed with the key of its public key.
+    pub fn submit_reveal_part(
+        &mut self,
+        part: S::RevealPart,
+        proof: S::RevealProof,
+    ) -> Result<(), DeckError> {
         if let DeckStatus::Revealing {
             card_id,
             receiver,
             turn,
-            progress: _,
+            progress,
         } = self.status.clone()
         {
             let player_id = self.get_player_id()?;
@@ -232,6 +497,15 @@ impl Deck {
                 return Err(DeckError::PlayerCantReveal);
             }
 
+            let public_key = &self.public_keys[player_id as usize];
+            let card = S::reveal(public_key, &progress, &part, &proof)
+                .ok_or(DeckError::InvalidRevealProof)?;
+            self.history.push(Step::Reveal {
+                player_id,
+                progress,
+                part,
+            });
+
             let mut next_turn = turn + 1;
 
             if let Some(receiver) = receiver {
@@ -291,3 +565,340 @@ impl Deck {
         }
     }
 }
+
+/// Deck of a room, using the scheme selected when the room was created.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
+pub enum AnyDeck {
+    Sra(Deck<Sra>),
+    ElGamal(Deck<ElGamal>),
+}
+
+impl AnyDeck {
+    pub fn new(scheme: CardScheme, num_cards: u64) -> Self {
+        match scheme {
+            CardScheme::Sra => AnyDeck::Sra(Deck::new(num_cards)),
+            CardScheme::ElGamal => AnyDeck::ElGamal(Deck::new(num_cards)),
+        }
+    }
+
+    pub fn get_players(&self) -> Vec<AccountId> {
+        match self {
+            AnyDeck::Sra(deck) => deck.get_players(),
+            AnyDeck::ElGamal(deck) => deck.get_players(),
+        }
+    }
+
+    pub fn num_players(&self) -> u64 {
+        match self {
+            AnyDeck::Sra(deck) => deck.num_players(),
+            AnyDeck::ElGamal(deck) => deck.num_players(),
+        }
+    }
+
+    pub fn get_player_id(&self) -> Result<PlayerId, DeckError> {
+        match self {
+            AnyDeck::Sra(deck) => deck.get_player_id(),
+            AnyDeck::ElGamal(deck) => deck.get_player_id(),
+        }
+    }
+
+    pub fn enter(&mut self, account_id: AccountId, public_key: PublicKey) -> Result<(), DeckError> {
+        match (self, public_key) {
+            (AnyDeck::Sra(deck), PublicKey::Sra(public_key)) => deck.enter(account_id, public_key),
+            (AnyDeck::ElGamal(deck), PublicKey::ElGamal(public_key)) => {
+                deck.enter(account_id, public_key)
+            }
+            _ => Err(DeckError::InvalidScheme),
+        }
+    }
+
+    pub fn remove_player(&mut self, player_id: PlayerId) -> Result<AccountId, DeckError> {
+        match self {
+            AnyDeck::Sra(deck) => deck.remove_player(player_id),
+            AnyDeck::ElGamal(deck) => deck.remove_player(player_id),
+        }
+    }
+
+    pub fn start(&mut self) -> Result<(), DeckError> {
+        match self {
+            AnyDeck::Sra(deck) => deck.start(),
+            AnyDeck::ElGamal(deck) => deck.start(),
+        }
+    }
+
+    /// Whether no player is expected to act on the deck.
+    pub fn is_running(&self) -> bool {
+        match self {
+            AnyDeck::Sra(deck) => deck.get_status() == DeckStatus::Running,
+            AnyDeck::ElGamal(deck) => deck.get_status() == DeckStatus::Running,
+        }
+    }
+
+    pub fn get_turn(&self) -> Option<PlayerId> {
+        match self {
+            AnyDeck::Sra(deck) => deck.get_turn(),
+            AnyDeck::ElGamal(deck) => deck.get_turn(),
+        }
+    }
+
+    pub fn revealed_cards(&self) -> Vec<Option<u64>> {
+        match self {
+            AnyDeck::Sra(deck) => deck.revealed_cards(),
+            AnyDeck::ElGamal(deck) => deck.revealed_cards(),
+        }
+    }
+
+    pub fn get_partial_shuffle(&self) -> Result<Cards, DeckError> {
+        match self {
+            AnyDeck::Sra(deck) => deck.get_partial_shuffle().map(Cards::Sra),
+            AnyDeck::ElGamal(deck) => deck.get_partial_shuffle().map(Cards::ElGamal),
+        }
+    }
+
+    pub fn close(&mut self) -> Vec<AccountId> {
+        match self {
+            AnyDeck::Sra(deck) => deck.close(),
+            AnyDeck::ElGamal(deck) => deck.close(),
+        }
+    }
+
+    pub fn submit_shuffled(
+        &mut self,
+        new_cards: Cards,
+        proof: ShuffleProof,
+    ) -> Result<(), DeckError> {
+        match (self, new_cards, proof) {
+            (AnyDeck::Sra(deck), Cards::Sra(new_cards), ShuffleProof::Sra(proof)) => {
+                deck.submit_shuffled(new_cards, proof)
+            }
+            (AnyDeck::ElGamal(deck), Cards::ElGamal(new_cards), ShuffleProof::ElGamal(proof)) => {
+                deck.submit_shuffled(new_cards, proof)
+            }
+            _ => Err(DeckError::InvalidScheme),
+        }
+    }
+
+    pub fn reveal_card(
+        &mut self,
+        card_id: u64,
+        receiver_player_id: Option<PlayerId>,
+    ) -> Result<(), DeckError> {
+        match self {
+            AnyDeck::Sra(deck) => deck.reveal_card(card_id, receiver_player_id),
+            AnyDeck::ElGamal(deck) => deck.reveal_card(card_id, receiver_player_id),
+        }
+    }
+
+    pub fn submit_reveal_part(
+        &mut self,
+        part: RevealPart,
+        proof: RevealProof,
+    ) -> Result<(), DeckError> {
+        match (self, part, proof) {
+            (AnyDeck::Sra(deck), RevealPart::Sra(part), RevealProof::Sra(proof)) => {
+                deck.submit_reveal_part(part, proof)
+            }
+            (AnyDeck::ElGamal(deck), RevealPart::ElGamal(part), RevealProof::ElGamal(proof)) => {
+                deck.submit_reveal_part(part, proof)
+            }
+            _ => Err(DeckError::InvalidScheme),
+        }
+    }
+
+    pub fn finish_reveal(&mut self) -> Result<(), DeckError> {
+        match self {
+            AnyDeck::Sra(deck) => deck.finish_reveal(),
+            AnyDeck::ElGamal(deck) => deck.finish_reveal(),
+        }
+    }
+
+    pub fn is_secret_key_pending(&self, account_id: &AccountId) -> bool {
+        match self {
+            AnyDeck::Sra(deck) => deck.is_secret_key_pending(account_id),
+            AnyDeck::ElGamal(deck) => deck.is_secret_key_pending(account_id),
+        }
+    }
+
+    pub fn submit_secret_key(
+        &mut self,
+        secret_key: SecretKey,
+        public_key: Option<PublicKey>,
+    ) -> Result<Option<AuditResult>, DeckError> {
+        match (self, secret_key, public_key) {
+            (AnyDeck::Sra(deck), SecretKey::Sra(secret_key), None) => {
+                deck.submit_secret_key(secret_key, None)
+            }
+            (AnyDeck::Sra(deck), SecretKey::Sra(secret_key), Some(PublicKey::Sra(public_key))) => {
+                deck.submit_secret_key(secret_key, Some(public_key))
+            }
+            (AnyDeck::ElGamal(deck), SecretKey::ElGamal(secret_key), None) => {
+                deck.submit_secret_key(secret_key, None)
+            }
+            (
+                AnyDeck::ElGamal(deck),
+                SecretKey::ElGamal(secret_key),
+                Some(PublicKey::ElGamal(public_key)),
+            ) => deck.submit_secret_key(secret_key, Some(public_key)),
+            _ => Err(DeckError::InvalidScheme),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bigint::U256;
+    use crate::sra::{self, GENERATOR};
+    use crate::test_utils::{set_signer, Rng};
+
+    const PLAYERS: [&str; 3] = ["alice", "bob", "carol"];
+
+    fn public_key(secret_key: &U256) -> U256 {
+        sra::pow_mod(&GENERATOR, secret_key)
+    }
+
+    /// Deck with every player seated. Return the secret key of each player.
+    fn new_deck(rng: &mut Rng) -> (Deck<Sra>, Vec<U256>) {
+        let mut deck = Deck::new(5);
+        let mut secret_keys = vec![];
+        for account_id in PLAYERS.iter() {
+            let secret_key = sra::tests::random_key(rng);
+            deck.enter(account_id.to_string(), public_key(&secret_key))
+                .unwrap();
+            secret_keys.push(secret_key);
+        }
+        (deck, secret_keys)
+    }
+
+    /// Play a hand where card 0 is revealed to bob and card 1 to everyone, and close it.
+    /// Return the players that withheld the key of the previous hand.
+    fn play_hand(rng: &mut Rng, deck: &mut Deck<Sra>, secret_keys: &[U256]) -> Vec<AccountId> {
+        deck.start().unwrap();
+        for (account_id, secret_key) in PLAYERS.iter().zip(secret_keys.iter()) {
+            set_signer(account_id);
+            let cards = deck.get_partial_shuffle().unwrap();
+            let (new_cards, proof) = sra::tests::shuffle(rng, secret_key, &cards);
+            deck.submit_shuffled(new_cards, proof).unwrap();
+        }
+
+        for (card_id, receiver) in [(0, Some(1)), (1, None)].iter() {
+            deck.reveal_card(*card_id, *receiver).unwrap();
+            while let DeckStatus::Revealing { turn, progress, .. } = deck.get_status() {
+                set_signer(PLAYERS[turn as usize]);
+                if *receiver == Some(turn) {
+                    deck.finish_reveal().unwrap();
+                } else {
+                    let (part, proof) =
+                        sra::tests::reveal(rng, &secret_keys[turn as usize], &progress);
+                    deck.submit_reveal_part(part, proof).unwrap();
+                }
+            }
+        }
+        deck.close()
+    }
+
+    /// Reveal the secret key of `player_id` and replace it with a new one.
+    fn submit_secret_key(
+        rng: &mut Rng,
+        deck: &mut Deck<Sra>,
+        secret_keys: &mut [U256],
+        player_id: usize,
+    ) -> Option<AuditResult> {
+        set_signer(PLAYERS[player_id]);
+        let new_key = sra::tests::random_key(rng);
+        let result = deck
+            .submit_secret_key(secret_keys[player_id], Some(public_key(&new_key)))
+            .unwrap();
+        secret_keys[player_id] = new_key;
+        result
+    }
+
+    #[test]
+    fn audit_clean_hand() {
+        let mut rng = Rng::new(1);
+        let (mut deck, mut secret_keys) = new_deck(&mut rng);
+        assert!(play_hand(&mut rng, &mut deck, &secret_keys).is_empty());
+
+        assert_eq!(
+            submit_secret_key(&mut rng, &mut deck, &mut secret_keys, 2),
+            None
+        );
+        assert_eq!(
+            submit_secret_key(&mut rng, &mut deck, &mut secret_keys, 0),
+            None
+        );
+        assert!(deck.is_secret_key_pending(&"bob".to_string()));
+        assert_eq!(
+            submit_secret_key(&mut rng, &mut deck, &mut secret_keys, 1),
+            Some(AuditResult::Valid)
+        );
+        assert!(!deck.is_secret_key_pending(&"bob".to_string()));
+
+        // The next hand is played with the new keys.
+        assert!(play_hand(&mut rng, &mut deck, &secret_keys).is_empty());
+    }
+
+    #[test]
+    fn audit_finds_cheater_before_every_key() {
+        let mut rng = Rng::new(2);
+        let (mut deck, mut secret_keys) = new_deck(&mut rng);
+        play_hand(&mut rng, &mut deck, &secret_keys);
+
+        // Proofs can't be forged, so tamper with the cards bob submitted once they are in
+        // the audit instead.
+        match &mut deck.audit.as_mut().unwrap().steps[1] {
+            Step::Shuffle { player_id, cards } => {
+                assert_eq!(*player_id, 1);
+                cards[0] = cards[1];
+            }
+            Step::Reveal { .. } => panic!("Second step should be the shuffle of bob."),
+        }
+
+        // Only the submissions of alice can be checked with her key.
+        assert_eq!(
+            submit_secret_key(&mut rng, &mut deck, &mut secret_keys, 0),
+            None
+        );
+        let cheated = Some(AuditResult::Cheated {
+            step: 1,
+            player_id: 1,
+            account_id: "bob".to_string(),
+        });
+        assert_eq!(
+            submit_secret_key(&mut rng, &mut deck, &mut secret_keys, 1),
+            cheated
+        );
+
+        // The cheater is reported once, but the key of carol is still expected.
+        assert!(deck.is_secret_key_pending(&"carol".to_string()));
+        assert_eq!(
+            submit_secret_key(&mut rng, &mut deck, &mut secret_keys, 2),
+            None
+        );
+        assert_eq!(deck.audit.unwrap().result, cheated);
+    }
+
+    #[test]
+    fn audit_withheld_key() {
+        let mut rng = Rng::new(3);
+        let (mut deck, mut secret_keys) = new_deck(&mut rng);
+        play_hand(&mut rng, &mut deck, &secret_keys);
+
+        assert_eq!(
+            submit_secret_key(&mut rng, &mut deck, &mut secret_keys, 0),
+            None
+        );
+        assert_eq!(
+            submit_secret_key(&mut rng, &mut deck, &mut secret_keys, 1),
+            None
+        );
+        assert!(deck.is_secret_key_pending(&"carol".to_string()));
+
+        // Carol keeps playing with her old key, and never reveals it.
+        assert_eq!(
+            play_hand(&mut rng, &mut deck, &secret_keys),
+            vec!["carol".to_string()]
+        );
+        assert!(deck.is_secret_key_pending(&"alice".to_string()));
+    }
+}
@@ -0,0 +1,1156 @@
+use crate::hand::DECK_SIZE;
+use crate::scheme::Scheme;
+use crate::types::{hex_format, PlayerId};
+use borsh::{BorshDeserialize, BorshSerialize};
+use curve25519_dalek::constants::{RISTRETTO_BASEPOINT_POINT, RISTRETTO_BASEPOINT_TABLE};
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{Identity, IsIdentity, VartimeMultiscalarMul};
+use near_bindgen::env;
+use serde::{Deserialize, Serialize};
+use std::iter;
+
+/// Compressed Ristretto255 point. Serialized as hex in JSON.
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug,
+)]
+pub struct EncodedPoint(#[serde(with = "hex_format")] pub [u8; 32]);
+
+impl EncodedPoint {
+    fn new(point: &RistrettoPoint) -> Self {
+        EncodedPoint(point.compress().to_bytes())
+    }
+
+    /// None if it is not a valid encoding of a point.
+    fn decode(&self) -> Option<RistrettoPoint> {
+        CompressedRistretto(self.0).decompress()
+    }
+}
+
+/// Scalar modulo the order of Ristretto255, as 32 bytes little endian. Serialized as hex
+/// in JSON.
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug,
+)]
+pub struct EncodedScalar(#[serde(with = "hex_format")] pub [u8; 32]);
+
+impl EncodedScalar {
+    /// None if the value is not lower than the order of the group.
+    fn decode(&self) -> Option<Scalar> {
+        Scalar::from_canonical_bytes(self.0)
+    }
+}
+
+/// Card masked with the joint key `H` of the players, `(r * G, card + r * H)` where `G`
+/// is the Ristretto255 basepoint. Cards start unmasked with `r = 0`.
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug,
+)]
+pub struct Ciphertext {
+    pub mask: EncodedPoint,
+    pub value: EncodedPoint,
+}
+
+impl Ciphertext {
+    fn decode(&self) -> Option<(RistrettoPoint, RistrettoPoint)> {
+        Some((self.mask.decode()?, self.value.decode()?))
+    }
+}
+
+/// Public key `x * G` of a player with a Schnorr proof that it knows `x`, so that no
+/// player can pick its key depending on the keys of the others and learn the secret key
+/// of the joint key.
+///
+/// The prover picks a random `w` and commits with `commitment = w * G`. The challenge `c`
+/// is the sha256 of `G`, `key` and `commitment` reduced modulo the order of the group. The
+/// response is `w + c * x`.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+pub struct PublicKey {
+    pub key: EncodedPoint,
+    pub commitment: EncodedPoint,
+    pub response: EncodedScalar,
+}
+
+/// Shuffle argument of Terelius and Wikström that the new deck is a permutation of the
+/// previous deck masked again with the joint key `H`, `new[i] = previous[p(i)]` plus
+/// `(m_i * G, m_i * H)` for random `m_i`. Its size and the cost to verify it grow linearly
+/// with the number of cards `n`.
+///
+/// Points and scalars are written as their 32 bytes when hashed, and ciphertexts as their
+/// mask followed by their value. `h`, `h_0..h_(n-1)` are the `commitment_generators`.
+/// - `permutation_commitments[j] = r_j * G + h_i` where `p(i) = j`, with random `r_j`.
+/// - `seed` is the sha256 of the joint key, the previous deck, the new deck and the
+///   permutation commitments. Challenge `u_j` is the first 16 bytes, little endian, of the
+///   sha256 of `seed` followed by `j` as 8 bytes little endian, and `v_i = u_p(i)`.
+/// - `chain[i] = s_i * G + v_i * chain[i - 1]` with random `s_i`, where `chain[-1] = h`.
+/// - With random `w_1..w_4`, `w'_i` and `w''_i`, `commitments` are `t_1 = w_1 * G`,
+///   `t_2 = w_2 * G` and `t_3 = w_3 * G + sum(w''_i * h_i)`. `deck_commitment` is
+///   `sum(w''_i * new[i])` minus `(w_4 * G, w_4 * H)`, and
+///   `chain_commitments[i] = w'_i * G + w''_i * chain[i - 1]`.
+/// - Challenge `c` is the first 16 bytes, little endian, of the sha256 of `seed`, the
+///   chain, the commitments, the deck commitment and the chain commitments.
+/// - `responses` are `w_1 + c * sum(r_j)`, `w_2 + c * sum(s_i * v_(i+1) * .. * v_(n-1))`,
+///   `w_3 + c * sum(r_j * u_j)` and `w_4 + c * sum(m_i * v_i)`.
+///   `chain_responses[i] = w'_i + c * s_i` and `permuted_responses[i] = w''_i + c * v_i`.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+pub struct ShuffleProof {
+    pub permutation_commitments: Vec<EncodedPoint>,
+    pub chain: Vec<EncodedPoint>,
+    pub commitments: Vec<EncodedPoint>,
+    pub deck_commitment: Ciphertext,
+    pub chain_commitments: Vec<EncodedPoint>,
+    pub responses: Vec<EncodedScalar>,
+    pub chain_responses: Vec<EncodedScalar>,
+    pub permuted_responses: Vec<EncodedScalar>,
+}
+
+/// Chaum-Pedersen proof that a decryption share `d` was computed with the secret key `x`
+/// of the player, that is, `d = x * mask` and `key = x * G`.
+///
+/// The prover picks a random `w` and commits with `base_commitment = w * G` and
+/// `card_commitment = w * mask`. The challenge `c` is the sha256 of `G`, the public key,
+/// `mask`, `d` and both commitments reduced modulo the order of the group. The response
+/// is `w + c * x`.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+pub struct RevealProof {
+    pub base_commitment: EncodedPoint,
+    pub card_commitment: EncodedPoint,
+    pub response: EncodedScalar,
+}
+
+fn hash(points: &[EncodedPoint]) -> Vec<u8> {
+    let bytes = points
+        .iter()
+        .flat_map(|point| point.0.iter().copied())
+        .collect::<Vec<_>>();
+    env::sha256(&bytes)
+}
+
+fn challenge_scalar(points: &[EncodedPoint]) -> Scalar {
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&hash(points));
+    Scalar::from_bytes_mod_order(bytes)
+}
+
+/// Encoding of every card of a deck, so that revealed cards are decoded without
+/// computing `encode_card` for each of them.
+const ENCODED_CARDS: [[u8; 32]; DECK_SIZE as usize] = [
+    [
+        0xe2, 0xf2, 0xae, 0x0a, 0x6a, 0xbc, 0x4e, 0x71, 0xa8, 0x84, 0xa9, 0x61, 0xc5, 0x00, 0x51,
+        0x5f, 0x58, 0xe3, 0x0b, 0x6a, 0xa5, 0x82, 0xdd, 0x8d, 0xb6, 0xa6, 0x59, 0x45, 0xe0, 0x8d,
+        0x2d, 0x76,
+    ],
+    [
+        0x6a, 0x49, 0x32, 0x10, 0xf7, 0x49, 0x9c, 0xd1, 0x7f, 0xec, 0xb5, 0x10, 0xae, 0x0c, 0xea,
+        0x23, 0xa1, 0x10, 0xe8, 0xd5, 0xb9, 0x01, 0xf8, 0xac, 0xad, 0xd3, 0x09, 0x5c, 0x73, 0xa3,
+        0xb9, 0x19,
+    ],
+    [
+        0x94, 0x74, 0x1f, 0x5d, 0x5d, 0x52, 0x75, 0x5e, 0xce, 0x4f, 0x23, 0xf0, 0x44, 0xee, 0x27,
+        0xd5, 0xd1, 0xea, 0x1e, 0x2b, 0xd1, 0x96, 0xb4, 0x62, 0x16, 0x6b, 0x16, 0x15, 0x2a, 0x9d,
+        0x02, 0x59,
+    ],
+    [
+        0xda, 0x80, 0x86, 0x27, 0x73, 0x35, 0x8b, 0x46, 0x6f, 0xfa, 0xdf, 0xe0, 0xb3, 0x29, 0x3a,
+        0xb3, 0xd9, 0xfd, 0x53, 0xc5, 0xea, 0x6c, 0x95, 0x53, 0x58, 0xf5, 0x68, 0x32, 0x2d, 0xaf,
+        0x6a, 0x57,
+    ],
+    [
+        0xe8, 0x82, 0xb1, 0x31, 0x01, 0x6b, 0x52, 0xc1, 0xd3, 0x33, 0x70, 0x80, 0x18, 0x7c, 0xf7,
+        0x68, 0x42, 0x3e, 0xfc, 0xcb, 0xb5, 0x17, 0xbb, 0x49, 0x5a, 0xb8, 0x12, 0xc4, 0x16, 0x0f,
+        0xf4, 0x4e,
+    ],
+    [
+        0xf6, 0x47, 0x46, 0xd3, 0xc9, 0x2b, 0x13, 0x05, 0x0e, 0xd8, 0xd8, 0x02, 0x36, 0xa7, 0xf0,
+        0x00, 0x7c, 0x3b, 0x3f, 0x96, 0x2f, 0x5b, 0xa7, 0x93, 0xd1, 0x9a, 0x60, 0x1e, 0xbb, 0x1d,
+        0xf4, 0x03,
+    ],
+    [
+        0x44, 0xf5, 0x35, 0x20, 0x92, 0x6e, 0xc8, 0x1f, 0xbd, 0x5a, 0x38, 0x78, 0x45, 0xbe, 0xb7,
+        0xdf, 0x85, 0xa9, 0x6a, 0x24, 0xec, 0xe1, 0x87, 0x38, 0xbd, 0xcf, 0xa6, 0xa7, 0x82, 0x2a,
+        0x17, 0x6d,
+    ],
+    [
+        0x90, 0x32, 0x93, 0xd8, 0xf2, 0x28, 0x7e, 0xbe, 0x10, 0xe2, 0x37, 0x4d, 0xc1, 0xa5, 0x3e,
+        0x0b, 0xc8, 0x87, 0xe5, 0x92, 0x69, 0x9f, 0x02, 0xd0, 0x77, 0xd5, 0x26, 0x3c, 0xdd, 0x55,
+        0x60, 0x1c,
+    ],
+    [
+        0x02, 0x62, 0x2a, 0xce, 0x8f, 0x73, 0x03, 0xa3, 0x1c, 0xaf, 0xc6, 0x3f, 0x8f, 0xc4, 0x8f,
+        0xdc, 0x16, 0xe1, 0xc8, 0xc8, 0xd2, 0x34, 0xb2, 0xf0, 0xd6, 0x68, 0x52, 0x82, 0xa9, 0x07,
+        0x60, 0x31,
+    ],
+    [
+        0x20, 0x70, 0x6f, 0xd7, 0x88, 0xb2, 0x72, 0x0a, 0x1e, 0xd2, 0xa5, 0xda, 0xd4, 0x95, 0x2b,
+        0x01, 0xf4, 0x13, 0xbc, 0xf0, 0xe7, 0x56, 0x4d, 0xe8, 0xcd, 0xc8, 0x16, 0x68, 0x9e, 0x2d,
+        0xb9, 0x5f,
+    ],
+    [
+        0xbc, 0xe8, 0x3f, 0x8b, 0xa5, 0xdd, 0x2f, 0xa5, 0x72, 0x86, 0x4c, 0x24, 0xba, 0x18, 0x10,
+        0xf9, 0x52, 0x2b, 0xc6, 0x00, 0x4a, 0xfe, 0x95, 0x87, 0x7a, 0xc7, 0x32, 0x41, 0xca, 0xfd,
+        0xab, 0x42,
+    ],
+    [
+        0xe4, 0x54, 0x9e, 0xe1, 0x6b, 0x9a, 0xa0, 0x30, 0x99, 0xca, 0x20, 0x8c, 0x67, 0xad, 0xaf,
+        0xca, 0xfa, 0x4c, 0x3f, 0x3e, 0x4e, 0x53, 0x03, 0xde, 0x60, 0x26, 0xe3, 0xca, 0x8f, 0xf8,
+        0x44, 0x60,
+    ],
+    [
+        0xaa, 0x52, 0xe0, 0x00, 0xdf, 0x2e, 0x16, 0xf5, 0x5f, 0xb1, 0x03, 0x2f, 0xc3, 0x3b, 0xc4,
+        0x27, 0x42, 0xda, 0xd6, 0xbd, 0x5a, 0x8f, 0xc0, 0xbe, 0x01, 0x67, 0x43, 0x6c, 0x59, 0x48,
+        0x50, 0x1f,
+    ],
+    [
+        0x46, 0x37, 0x6b, 0x80, 0xf4, 0x09, 0xb2, 0x9d, 0xc2, 0xb5, 0xf6, 0xf0, 0xc5, 0x25, 0x91,
+        0x99, 0x08, 0x96, 0xe5, 0x71, 0x6f, 0x41, 0x47, 0x7c, 0xd3, 0x00, 0x85, 0xab, 0x7f, 0x10,
+        0x30, 0x1e,
+    ],
+    [
+        0xe0, 0xc4, 0x18, 0xf7, 0xc8, 0xd9, 0xc4, 0xcd, 0xd7, 0x39, 0x5b, 0x93, 0xea, 0x12, 0x4f,
+        0x3a, 0xd9, 0x90, 0x21, 0xbb, 0x68, 0x1d, 0xfc, 0x33, 0x02, 0xa9, 0xd9, 0x9a, 0x2e, 0x53,
+        0xe6, 0x4e,
+    ],
+    [
+        0xc8, 0x62, 0xfc, 0xed, 0x13, 0x14, 0xe8, 0x1e, 0x9b, 0x77, 0xd0, 0x2b, 0x84, 0x76, 0x89,
+        0x09, 0x6b, 0x4e, 0x7d, 0xed, 0x39, 0xb0, 0x09, 0xb9, 0xc9, 0x96, 0x98, 0x2e, 0x4e, 0xca,
+        0xc6, 0x6e,
+    ],
+    [
+        0x68, 0x28, 0x02, 0xb3, 0xc9, 0x01, 0x12, 0xe0, 0xf4, 0xe7, 0xd9, 0x85, 0xe4, 0x23, 0xcd,
+        0x2b, 0x16, 0xc5, 0xbf, 0xa6, 0x3d, 0x9c, 0x96, 0x7c, 0x52, 0xbb, 0x6c, 0xb7, 0xfe, 0xa7,
+        0xea, 0x7e,
+    ],
+    [
+        0x82, 0xe5, 0xde, 0x81, 0x9f, 0x5d, 0x2e, 0x9b, 0x6e, 0xd6, 0xa3, 0x33, 0x8e, 0xa3, 0xa7,
+        0xf6, 0x20, 0x13, 0x61, 0xb6, 0x5e, 0x13, 0xd6, 0x83, 0x24, 0x33, 0xc4, 0x19, 0xca, 0xf0,
+        0x1a, 0x1b,
+    ],
+    [
+        0x4c, 0xf1, 0xb9, 0xde, 0xda, 0x93, 0xeb, 0x9f, 0xd5, 0x15, 0xfc, 0xc9, 0x92, 0x62, 0xae,
+        0xd1, 0x36, 0x8b, 0x48, 0xf2, 0x4a, 0x27, 0xaf, 0xd2, 0x98, 0x4d, 0xa8, 0xfe, 0x7b, 0xb2,
+        0x34, 0x1f,
+    ],
+    [
+        0xee, 0x01, 0x6f, 0xbb, 0xdd, 0xe5, 0x40, 0x77, 0xfd, 0xa6, 0x9f, 0xec, 0xb5, 0x46, 0xe0,
+        0xa9, 0x3b, 0x1f, 0x4f, 0x03, 0xb1, 0xcf, 0xec, 0xf6, 0xfc, 0x5b, 0xde, 0x92, 0x0f, 0x61,
+        0xe9, 0x61,
+    ],
+    [
+        0xe6, 0xfc, 0xd7, 0x34, 0x1e, 0x95, 0xaf, 0xc3, 0xec, 0xd9, 0xcd, 0x47, 0x89, 0x2b, 0xf7,
+        0x83, 0xa6, 0xbe, 0x7b, 0x69, 0xd7, 0x00, 0xa7, 0xf5, 0x76, 0xad, 0xdc, 0x10, 0xeb, 0x7a,
+        0x12, 0x2b,
+    ],
+    [
+        0xd8, 0x86, 0x64, 0x1e, 0x16, 0xa1, 0x16, 0x5d, 0x70, 0xfa, 0x89, 0x41, 0x3c, 0x41, 0x29,
+        0xd5, 0x6b, 0x15, 0xd5, 0xf4, 0x4d, 0x2d, 0xd2, 0xb0, 0x98, 0x23, 0xcd, 0x72, 0x34, 0x87,
+        0x65, 0x6a,
+    ],
+    [
+        0xc0, 0x28, 0x7a, 0xb3, 0x50, 0x2a, 0x0f, 0x5c, 0x58, 0x53, 0xeb, 0xaa, 0x19, 0x1d, 0x8b,
+        0x01, 0xc4, 0x2c, 0xdc, 0x8c, 0x12, 0x4c, 0x3c, 0xc7, 0x60, 0x30, 0xee, 0x08, 0xdd, 0xab,
+        0x85, 0x59,
+    ],
+    [
+        0x8e, 0xbe, 0x6b, 0xc9, 0x29, 0x23, 0x16, 0x56, 0x88, 0x3c, 0xfc, 0x38, 0x42, 0x90, 0xb5,
+        0x24, 0x38, 0xc7, 0x16, 0xf5, 0x91, 0x25, 0x35, 0x84, 0x1e, 0x92, 0xf6, 0x81, 0x54, 0xb9,
+        0x38, 0x4f,
+    ],
+    [
+        0xaa, 0x28, 0x4c, 0x17, 0xcc, 0xc8, 0xe5, 0xf3, 0x7a, 0x1c, 0xe1, 0x35, 0xd2, 0x87, 0x97,
+        0xe0, 0x88, 0x67, 0xfe, 0x1b, 0x93, 0x2f, 0xbf, 0x4f, 0x79, 0x0c, 0x0b, 0xec, 0x5c, 0xfd,
+        0x45, 0x40,
+    ],
+    [
+        0x6c, 0xc0, 0xa9, 0x29, 0x86, 0x0a, 0x63, 0x0d, 0xee, 0x30, 0x30, 0xbe, 0x2f, 0x2e, 0xa4,
+        0xd5, 0xfb, 0xe3, 0xf1, 0x51, 0x1c, 0xc0, 0xc1, 0xbc, 0x94, 0xc4, 0x51, 0xfd, 0x61, 0xf3,
+        0x6d, 0x7c,
+    ],
+    [
+        0x88, 0x75, 0xa1, 0xf1, 0x37, 0xb0, 0x86, 0x40, 0xce, 0x57, 0xa6, 0xc8, 0x82, 0x9c, 0xd2,
+        0xa1, 0xd8, 0x10, 0x2a, 0xd8, 0x53, 0xb6, 0x0c, 0xec, 0x13, 0xfc, 0x90, 0x1a, 0x14, 0xa7,
+        0xf0, 0x7b,
+    ],
+    [
+        0x6c, 0xe1, 0x75, 0x3d, 0x32, 0xf3, 0x79, 0x74, 0x82, 0x9e, 0x1d, 0x2c, 0x6d, 0xe6, 0xcc,
+        0xe3, 0xf3, 0x71, 0x7f, 0xe0, 0x44, 0x0b, 0x02, 0x47, 0xaf, 0xb6, 0x59, 0x69, 0x75, 0x51,
+        0x8f, 0x16,
+    ],
+    [
+        0x28, 0x09, 0xbe, 0x5a, 0x1c, 0x38, 0x8c, 0x4c, 0x00, 0x70, 0xa5, 0xc6, 0x6a, 0xce, 0x50,
+        0x7f, 0xea, 0xde, 0x48, 0x82, 0x85, 0x90, 0x31, 0x46, 0x74, 0xcb, 0x0a, 0x6f, 0xd9, 0x71,
+        0xe9, 0x03,
+    ],
+    [
+        0x46, 0x1d, 0x25, 0x98, 0xd7, 0xda, 0x2e, 0x1f, 0x67, 0xbf, 0x3a, 0xab, 0x17, 0xd1, 0x9d,
+        0x23, 0x80, 0x4b, 0xce, 0xfe, 0xda, 0x3d, 0x88, 0x15, 0xb8, 0x15, 0x79, 0x8a, 0x8d, 0x49,
+        0x71, 0x2c,
+    ],
+    [
+        0x18, 0x73, 0x3c, 0x1f, 0x1a, 0xd7, 0x91, 0x06, 0x71, 0x84, 0xa9, 0x07, 0x70, 0x02, 0x9a,
+        0x4d, 0x74, 0x69, 0x9b, 0x9f, 0x5d, 0x09, 0x8d, 0x50, 0xf8, 0x8a, 0xa9, 0xd8, 0xbb, 0xf8,
+        0xe8, 0x72,
+    ],
+    [
+        0xd8, 0x27, 0xa0, 0x80, 0x82, 0x88, 0xa3, 0xc1, 0xce, 0x91, 0x19, 0x2c, 0x07, 0x70, 0xc3,
+        0xad, 0x73, 0x72, 0xa5, 0x0a, 0xc6, 0x01, 0xdf, 0xf8, 0x32, 0x3a, 0x5b, 0xdd, 0xa1, 0x04,
+        0x32, 0x2f,
+    ],
+    [
+        0x6c, 0xb9, 0x25, 0x75, 0x24, 0x37, 0x36, 0x87, 0x10, 0x23, 0x53, 0x14, 0x96, 0x3a, 0x2d,
+        0x23, 0x75, 0x18, 0x98, 0xb5, 0x36, 0xca, 0xb9, 0xb9, 0x8a, 0x32, 0xba, 0xb5, 0x6a, 0xfe,
+        0xae, 0x45,
+    ],
+    [
+        0xa2, 0xa0, 0xc7, 0xd2, 0x94, 0x07, 0x48, 0xba, 0xa5, 0x8d, 0x9b, 0x65, 0xf7, 0xdf, 0x99,
+        0x87, 0x6a, 0xcb, 0xab, 0xf4, 0x56, 0x35, 0x79, 0x73, 0xbe, 0x10, 0x48, 0x2b, 0x69, 0x28,
+        0x78, 0x2a,
+    ],
+    [
+        0xae, 0x83, 0x13, 0x91, 0xaa, 0x3a, 0x7a, 0x39, 0x0a, 0x9b, 0xe0, 0x5e, 0x86, 0x3f, 0x21,
+        0xe5, 0xa5, 0x00, 0x33, 0xb8, 0x47, 0x09, 0x6c, 0xf7, 0x56, 0x5a, 0x46, 0x10, 0x50, 0xe1,
+        0xd9, 0x1e,
+    ],
+    [
+        0x6a, 0xb7, 0x9d, 0x1d, 0x77, 0xb9, 0xf2, 0x5e, 0x3c, 0x0e, 0xc9, 0x0b, 0x6f, 0xc4, 0x9c,
+        0xbb, 0x57, 0x6b, 0x76, 0xc3, 0x75, 0xf1, 0xe3, 0xc6, 0x84, 0x8a, 0xce, 0x9b, 0x9d, 0x3b,
+        0xf8, 0x6a,
+    ],
+    [
+        0xe4, 0x90, 0xd3, 0x1f, 0x3a, 0xc3, 0x63, 0x30, 0x33, 0xbf, 0xf8, 0x49, 0x5d, 0x51, 0x0e,
+        0xcd, 0x81, 0x97, 0xdb, 0x49, 0x99, 0x62, 0xd6, 0x84, 0x83, 0xa1, 0x34, 0xad, 0xe3, 0x50,
+        0x92, 0x3b,
+    ],
+    [
+        0xd2, 0x42, 0xad, 0x58, 0xe4, 0x7f, 0x39, 0x78, 0xb6, 0xde, 0x6e, 0xd8, 0xf3, 0x2d, 0xe5,
+        0xdc, 0x22, 0x16, 0x16, 0xee, 0x37, 0x50, 0x96, 0xb2, 0x06, 0xee, 0x26, 0x25, 0x3c, 0x35,
+        0x64, 0x26,
+    ],
+    [
+        0x5e, 0x7e, 0x80, 0x89, 0x54, 0x7c, 0x1f, 0x6f, 0x76, 0xa5, 0x2a, 0x8e, 0x9d, 0x44, 0x5e,
+        0x14, 0x97, 0xa3, 0xd0, 0x20, 0x01, 0xab, 0xcd, 0x47, 0x25, 0x16, 0x2e, 0xbc, 0x28, 0x80,
+        0x4b, 0x5e,
+    ],
+    [
+        0x3a, 0x2d, 0xb4, 0xd2, 0x8a, 0x56, 0x80, 0xe8, 0x9f, 0x59, 0x60, 0x32, 0x62, 0x65, 0x56,
+        0xb1, 0x4a, 0x28, 0x29, 0x02, 0x1c, 0x2b, 0x4b, 0x92, 0xd1, 0xd1, 0x51, 0x7a, 0x2a, 0x61,
+        0xf5, 0x30,
+    ],
+    [
+        0x1a, 0x07, 0xee, 0xbf, 0xf7, 0x9e, 0xaa, 0xfd, 0x93, 0xa8, 0x8e, 0x86, 0x67, 0xeb, 0x43,
+        0xea, 0x3c, 0xfa, 0x0d, 0x42, 0x6a, 0xb2, 0x38, 0x2d, 0x9f, 0x44, 0xa3, 0x6f, 0x44, 0x72,
+        0xd1, 0x19,
+    ],
+    [
+        0xe0, 0x0a, 0xf9, 0xc7, 0x4d, 0x9e, 0xdb, 0x8e, 0xbc, 0xc1, 0x60, 0xce, 0xec, 0x97, 0xd5,
+        0x31, 0xcb, 0xd6, 0xe2, 0x95, 0x6f, 0x9e, 0x91, 0x62, 0xb8, 0xe9, 0xed, 0xa2, 0x60, 0xe8,
+        0x2e, 0x43,
+    ],
+    [
+        0xa4, 0x83, 0xff, 0x09, 0x88, 0x7d, 0x5f, 0xd2, 0x4c, 0xbd, 0x44, 0x05, 0x20, 0x07, 0x10,
+        0x02, 0x93, 0xc6, 0xe6, 0xf2, 0xe7, 0x87, 0xf1, 0x66, 0x11, 0x9d, 0x3b, 0xbf, 0x0a, 0xfc,
+        0x4d, 0x42,
+    ],
+    [
+        0x2a, 0xbb, 0xaf, 0x93, 0x43, 0xc0, 0x9d, 0xe9, 0xb6, 0x33, 0x95, 0x41, 0x4e, 0x29, 0x8b,
+        0x3e, 0x30, 0xc1, 0x50, 0x7b, 0x6b, 0xe4, 0xa9, 0xe8, 0x3e, 0x27, 0x0e, 0x4f, 0xd1, 0xe6,
+        0xc4, 0x35,
+    ],
+    [
+        0x18, 0xde, 0x43, 0x8e, 0x0e, 0x41, 0x4e, 0x75, 0x83, 0xf4, 0x8a, 0xee, 0x69, 0x0b, 0x4e,
+        0x17, 0x56, 0xef, 0x8a, 0x94, 0xcc, 0xd9, 0xf3, 0x1d, 0xbc, 0x46, 0x6a, 0x9e, 0x1c, 0xfc,
+        0xa2, 0x14,
+    ],
+    [
+        0x7c, 0xb4, 0xf0, 0x8f, 0xec, 0xd2, 0xf9, 0x8d, 0xaa, 0xf2, 0x9f, 0x89, 0x78, 0x92, 0xe7,
+        0x9a, 0x51, 0x1a, 0xe5, 0x64, 0x9f, 0x21, 0x3b, 0xcd, 0x8d, 0xde, 0x15, 0x15, 0xa4, 0xd8,
+        0x7d, 0x36,
+    ],
+    [
+        0x1c, 0x5d, 0xaa, 0x6c, 0xa1, 0x14, 0x44, 0x5b, 0x07, 0x76, 0x86, 0x03, 0x8a, 0xae, 0x31,
+        0xc3, 0x1f, 0x2e, 0x14, 0x6a, 0x71, 0x45, 0xa2, 0xf3, 0xf9, 0x2b, 0x47, 0xc1, 0x31, 0xa1,
+        0x10, 0x31,
+    ],
+    [
+        0xce, 0x4f, 0x56, 0x5f, 0xcb, 0xf2, 0x61, 0x65, 0x8b, 0x2a, 0x33, 0x68, 0xf9, 0x7c, 0xf3,
+        0x3c, 0x0f, 0x08, 0x9e, 0x85, 0x3f, 0xe7, 0xdf, 0xdb, 0xa5, 0x55, 0x73, 0xef, 0x7d, 0x14,
+        0xf2, 0x65,
+    ],
+    [
+        0xce, 0xc1, 0x42, 0x6a, 0x33, 0x96, 0x5e, 0xb2, 0xa7, 0xd8, 0x2b, 0x28, 0x19, 0x64, 0xad,
+        0x39, 0xf0, 0x6d, 0x6f, 0xba, 0x7d, 0x8e, 0x57, 0xf8, 0xda, 0x4f, 0xcf, 0xef, 0xd9, 0x46,
+        0xd8, 0x55,
+    ],
+    [
+        0x9e, 0x50, 0x4f, 0x9b, 0x10, 0xc4, 0x02, 0x30, 0xec, 0x4e, 0x15, 0x70, 0xdc, 0xf2, 0x95,
+        0xd5, 0xda, 0x01, 0xaa, 0x0d, 0xae, 0xce, 0xd5, 0x73, 0x16, 0xb1, 0x60, 0xc8, 0x0b, 0xc5,
+        0x7e, 0x0c,
+    ],
+    [
+        0xa4, 0x5a, 0xf5, 0xc5, 0xee, 0xb3, 0xdb, 0x96, 0x87, 0xfe, 0x9e, 0xda, 0xe9, 0x53, 0x87,
+        0xf9, 0x1f, 0xf5, 0xa9, 0x0c, 0x44, 0x21, 0x59, 0x20, 0x4c, 0x3f, 0x97, 0x51, 0x4d, 0xdd,
+        0xad, 0x7c,
+    ],
+    [
+        0x30, 0xeb, 0x54, 0xee, 0x0d, 0x29, 0x0e, 0x0f, 0xd9, 0xf8, 0xa6, 0xc6, 0xcb, 0xc8, 0x4e,
+        0x3a, 0x51, 0x66, 0x45, 0xfe, 0x1b, 0xe7, 0x74, 0x29, 0x98, 0x73, 0x75, 0x49, 0x8a, 0xee,
+        0x86, 0x41,
+    ],
+];
+
+/// Encoding of `card` as a point, `(card + 1) * G`, so that no card is the identity.
+pub fn encode_card(card: u64) -> EncodedPoint {
+    EncodedPoint::new(&(&Scalar::from(card + 1) * &RISTRETTO_BASEPOINT_TABLE))
+}
+
+/// Sum of the keys of every player. Cards can only be unmasked with the help of all of
+/// them.
+fn joint_key(public_keys: &[PublicKey]) -> Option<RistrettoPoint> {
+    public_keys
+        .iter()
+        .try_fold(RistrettoPoint::identity(), |joint_key, public_key| {
+            Some(joint_key + public_key.key.decode()?)
+        })
+}
+
+fn decode_cards(cards: &[Ciphertext]) -> Option<Vec<(RistrettoPoint, RistrettoPoint)>> {
+    cards.iter().map(Ciphertext::decode).collect()
+}
+
+fn decode_points(points: &[EncodedPoint]) -> Option<Vec<RistrettoPoint>> {
+    points.iter().map(EncodedPoint::decode).collect()
+}
+
+fn decode_scalars(scalars: &[EncodedScalar]) -> Option<Vec<Scalar>> {
+    scalars.iter().map(EncodedScalar::decode).collect()
+}
+
+/// sha256 of `seed` followed by every value.
+fn hash_values<'a>(seed: &[u8], values: impl Iterator<Item = &'a [u8; 32]>) -> Vec<u8> {
+    let mut bytes = seed.to_vec();
+    for value in values {
+        bytes.extend_from_slice(value);
+    }
+    env::sha256(&bytes)
+}
+
+fn card_bytes(cards: &[Ciphertext]) -> impl Iterator<Item = &[u8; 32]> {
+    cards
+        .iter()
+        .flat_map(|card| iter::once(&card.mask.0).chain(iter::once(&card.value.0)))
+}
+
+/// First 16 bytes, little endian, of the sha256 of `seed` followed by `index` as 8 bytes
+/// little endian.
+fn derive_challenge(seed: &[u8], index: u64) -> Scalar {
+    let mut bytes = seed.to_vec();
+    bytes.extend_from_slice(&index.to_le_bytes());
+    let mut challenge = [0u8; 16];
+    challenge.copy_from_slice(&env::sha256(&bytes)[..16]);
+    Scalar::from(u128::from_le_bytes(challenge))
+}
+
+/// Generators `h, h_0, .., h_(count-1)` used to commit to a permutation, with no known
+/// relation between them. Generator `i` is mapped from 64 uniform bytes, the sha256 `x`
+/// of `poker shuffle generator` followed by `i` as 8 bytes little endian, and the sha256
+/// of `x`.
+pub fn commitment_generators(count: usize) -> Vec<RistrettoPoint> {
+    (0..=count as u64)
+        .map(|index| {
+            let mut bytes = b"poker shuffle generator".to_vec();
+            bytes.extend_from_slice(&index.to_le_bytes());
+            let first = env::sha256(&bytes);
+            let mut uniform = [0u8; 64];
+            uniform[..32].copy_from_slice(&first);
+            uniform[32..].copy_from_slice(&env::sha256(&first));
+            RistrettoPoint::from_uniform_bytes(&uniform)
+        })
+        .collect()
+}
+
+/// Verify that `new_cards` is a permutation of `previous` masked again with the joint
+/// key of `public_keys`. Every equation of the argument is checked at once, combining
+/// them with random weights in a single multiscalar multiplication.
+pub fn verify_shuffle(
+    public_keys: &[PublicKey],
+    previous: &[Ciphertext],
+    new_cards: &[Ciphertext],
+    proof: &ShuffleProof,
+) -> bool {
+    let n = previous.len();
+    if n == 0
+        || new_cards.len() != n
+        || proof.permutation_commitments.len() != n
+        || proof.chain.len() != n
+        || proof.commitments.len() != 3
+        || proof.chain_commitments.len() != n
+        || proof.responses.len() != 4
+        || proof.chain_responses.len() != n
+        || proof.permuted_responses.len() != n
+    {
+        return false;
+    }
+
+    let decoded = (
+        joint_key(public_keys),
+        decode_cards(previous),
+        decode_cards(new_cards),
+        decode_points(&proof.permutation_commitments),
+        decode_points(&proof.chain),
+        decode_points(&proof.commitments),
+        proof.deck_commitment.decode(),
+        decode_points(&proof.chain_commitments),
+    );
+    let (
+        joint_key,
+        previous_points,
+        new_points,
+        permutation_commitments,
+        chain,
+        commitments,
+        deck_commitment,
+        chain_commitments,
+    ) = match decoded {
+        (Some(a), Some(b), Some(c), Some(d), Some(e), Some(f), Some(g), Some(h)) => {
+            (a, b, c, d, e, f, g, h)
+        }
+        _ => return false,
+    };
+    let responses = (
+        decode_scalars(&proof.responses),
+        decode_scalars(&proof.chain_responses),
+        decode_scalars(&proof.permuted_responses),
+    );
+    let (z, z_chain, z_permuted) = match responses {
+        (Some(z), Some(z_chain), Some(z_permuted)) => (z, z_chain, z_permuted),
+        _ => return false,
+    };
+
+    let generators = commitment_generators(n);
+    let seed = hash_values(
+        &[],
+        iter::once(&EncodedPoint::new(&joint_key).0)
+            .chain(card_bytes(previous))
+            .chain(card_bytes(new_cards))
+            .chain(proof.permutation_commitments.iter().map(|point| &point.0)),
+    );
+    let u = (0..n as u64)
+        .map(|j| derive_challenge(&seed, j))
+        .collect::<Vec<_>>();
+    let challenge_hash = hash_values(
+        &seed,
+        proof
+            .chain
+            .iter()
+            .chain(proof.commitments.iter())
+            .map(|point| &point.0)
+            .chain(card_bytes(&[proof.deck_commitment]))
+            .chain(proof.chain_commitments.iter().map(|point| &point.0)),
+    );
+    let c = derive_challenge(&challenge_hash, 0);
+    let weights_seed = hash_values(
+        &challenge_hash,
+        proof
+            .responses
+            .iter()
+            .chain(proof.chain_responses.iter())
+            .chain(proof.permuted_responses.iter())
+            .map(|scalar| &scalar.0),
+    );
+    // Weights of the equations. The first one has weight 1.
+    let alpha = (0..4)
+        .map(|k| derive_challenge(&weights_seed, k))
+        .collect::<Vec<_>>();
+    let beta = (0..n as u64)
+        .map(|i| derive_challenge(&weights_seed, 4 + i))
+        .collect::<Vec<_>>();
+
+    let u_product = u.iter().product::<Scalar>();
+    let g_scalar = z[0] + alpha[0] * z[1] + alpha[1] * z[2] - alpha[2] * z[3]
+        + beta
+            .iter()
+            .zip(z_chain.iter())
+            .map(|(weight, response)| weight * response)
+            .sum::<Scalar>();
+
+    let mut scalars = vec![
+        g_scalar,
+        -alpha[3] * z[3],
+        alpha[0] * c * u_product + beta[0] * z_permuted[0],
+        -Scalar::one(),
+        -alpha[0],
+        -alpha[1],
+        -alpha[2],
+        -alpha[3],
+    ];
+    let mut points = vec![
+        RISTRETTO_BASEPOINT_POINT,
+        joint_key,
+        generators[0],
+        commitments[0],
+        commitments[1],
+        commitments[2],
+        deck_commitment.0,
+        deck_commitment.1,
+    ];
+    for i in 0..n {
+        let chain_scalar = if i + 1 < n {
+            beta[i + 1] * z_permuted[i + 1] - beta[i] * c
+        } else {
+            -(alpha[0] + beta[i]) * c
+        };
+        scalars.extend_from_slice(&[
+            c + alpha[1] * z_permuted[i],
+            -(c + alpha[1] * c * u[i]),
+            chain_scalar,
+            -beta[i],
+            alpha[2] * z_permuted[i],
+            alpha[3] * z_permuted[i],
+            -alpha[2] * c * u[i],
+            -alpha[3] * c * u[i],
+        ]);
+        points.extend_from_slice(&[
+            generators[i + 1],
+            permutation_commitments[i],
+            chain[i],
+            chain_commitments[i],
+            new_points[i].0,
+            new_points[i].1,
+            previous_points[i].0,
+            previous_points[i].1,
+        ]);
+    }
+
+    RistrettoPoint::vartime_multiscalar_mul(scalars, points).is_identity()
+}
+
+/// Threshold ElGamal over Ristretto255. Each player masks the cards again with the joint
+/// key while shuffling, and removes its decryption share from the cards to be revealed.
+#[derive(Clone, Default)]
+pub struct ElGamal;
+
+impl Scheme for ElGamal {
+    type PublicKey = PublicKey;
+    type Card = Ciphertext;
+    type ShuffleProof = ShuffleProof;
+    type RevealPart = EncodedPoint;
+    type RevealProof = RevealProof;
+    type SecretKey = EncodedScalar;
+
+    fn is_valid_public_key(public_key: &PublicKey) -> bool {
+        let decoded = (
+            public_key.key.decode(),
+            public_key.commitment.decode(),
+            public_key.response.decode(),
+        );
+        if let (Some(key), Some(commitment), Some(response)) = decoded {
+            let challenge = challenge_scalar(&[
+                EncodedPoint::new(&RISTRETTO_BASEPOINT_POINT),
+                public_key.key,
+                public_key.commitment,
+            ]);
+            key != RistrettoPoint::identity()
+                && &response * &RISTRETTO_BASEPOINT_TABLE == commitment + challenge * key
+        } else {
+            false
+        }
+    }
+
+    fn initial_cards(num_cards: u64) -> Vec<Ciphertext> {
+        let identity = EncodedPoint::new(&RistrettoPoint::identity());
+        (0..num_cards)
+            .map(|card| Ciphertext {
+                mask: identity,
+                value: encode_card(card),
+            })
+            .collect()
+    }
+
+    fn verify_shuffle(
+        public_keys: &[PublicKey],
+        _player_id: PlayerId,
+        previous: &[Ciphertext],
+        new_cards: &[Ciphertext],
+        proof: &ShuffleProof,
+    ) -> bool {
+        verify_shuffle(public_keys, previous, new_cards, proof)
+    }
+
+    fn reveal(
+        public_key: &PublicKey,
+        progress: &Ciphertext,
+        part: &EncodedPoint,
+        proof: &RevealProof,
+    ) -> Option<Ciphertext> {
+        let key = public_key.key.decode()?;
+        let (mask, value) = progress.decode()?;
+        let share = part.decode()?;
+        let base_commitment = proof.base_commitment.decode()?;
+        let card_commitment = proof.card_commitment.decode()?;
+        let response = proof.response.decode()?;
+
+        let challenge = challenge_scalar(&[
+            EncodedPoint::new(&RISTRETTO_BASEPOINT_POINT),
+            public_key.key,
+            progress.mask,
+            *part,
+            proof.base_commitment,
+            proof.card_commitment,
+        ]);
+
+        if &response * &RISTRETTO_BASEPOINT_TABLE == base_commitment + challenge * key
+            && response * mask == card_commitment + challenge * share
+        {
+            Some(Ciphertext {
+                mask: progress.mask,
+                value: EncodedPoint::new(&(value - share)),
+            })
+        } else {
+            None
+        }
+    }
+
+    fn is_secret_key(public_key: &PublicKey, secret_key: &EncodedScalar) -> bool {
+        secret_key.decode().map_or(false, |secret_key| {
+            EncodedPoint::new(&(&secret_key * &RISTRETTO_BASEPOINT_TABLE)) == public_key.key
+        })
+    }
+
+    fn replay_shuffle(
+        secret_keys: &[Option<EncodedScalar>],
+        _player_id: PlayerId,
+        previous: &[Ciphertext],
+        new_cards: &[Ciphertext],
+    ) -> Option<bool> {
+        // Cards are encrypted with the joint key, so every key is needed.
+        let secret_keys = secret_keys.iter().cloned().collect::<Option<Vec<_>>>()?;
+        let joint_secret_key = match secret_keys
+            .iter()
+            .map(EncodedScalar::decode)
+            .sum::<Option<Scalar>>()
+        {
+            Some(joint_secret_key) => joint_secret_key,
+            None => return Some(false),
+        };
+
+        let unmask = |cards: &[Ciphertext]| {
+            cards
+                .iter()
+                .map(|card| {
+                    let (mask, value) = card.decode()?;
+                    Some(EncodedPoint::new(&(value - joint_secret_key * mask)).0)
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(|mut cards| {
+                    cards.sort_unstable();
+                    cards
+                })
+        };
+
+        Some(match (unmask(previous), unmask(new_cards)) {
+            (Some(previous), Some(new_cards)) => previous == new_cards,
+            _ => false,
+        })
+    }
+
+    fn replay_reveal(
+        secret_key: &EncodedScalar,
+        progress: &Ciphertext,
+        part: &EncodedPoint,
+    ) -> bool {
+        match (secret_key.decode(), progress.mask.decode()) {
+            (Some(secret_key), Some(mask)) => EncodedPoint::new(&(secret_key * mask)) == *part,
+            _ => false,
+        }
+    }
+
+    fn decode_card(card: &Ciphertext, num_cards: u64) -> Option<u64> {
+        ENCODED_CARDS
+            .iter()
+            .take(num_cards as usize)
+            .position(|encoded| *encoded == card.value.0)
+            .map(|index| index as u64)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::test_utils::{set_signer, Rng};
+    use std::time::Instant;
+
+    pub fn random_scalar(rng: &mut Rng) -> Scalar {
+        Scalar::from_bytes_mod_order(rng.bytes())
+    }
+
+    pub fn public_key(rng: &mut Rng, secret_key: &Scalar) -> PublicKey {
+        let key = EncodedPoint::new(&(secret_key * &RISTRETTO_BASEPOINT_TABLE));
+        let w = random_scalar(rng);
+        let commitment = EncodedPoint::new(&(&w * &RISTRETTO_BASEPOINT_TABLE));
+        let challenge = challenge_scalar(&[
+            EncodedPoint::new(&RISTRETTO_BASEPOINT_POINT),
+            key,
+            commitment,
+        ]);
+        PublicKey {
+            key,
+            commitment,
+            response: EncodedScalar((w + challenge * secret_key).to_bytes()),
+        }
+    }
+
+    fn random_scalars(rng: &mut Rng, count: usize) -> Vec<Scalar> {
+        (0..count).map(|_| random_scalar(rng)).collect()
+    }
+
+    fn remask(card: &Ciphertext, mask: &Scalar, joint_key: &RistrettoPoint) -> Ciphertext {
+        let (card_mask, card_value) = card.decode().unwrap();
+        Ciphertext {
+            mask: EncodedPoint::new(&(card_mask + mask * &RISTRETTO_BASEPOINT_TABLE)),
+            value: EncodedPoint::new(&(card_value + mask * joint_key)),
+        }
+    }
+
+    /// Shuffle `previous` with `joint_key` and prove it as the client does.
+    pub fn shuffle(
+        rng: &mut Rng,
+        joint_key: &RistrettoPoint,
+        previous: &[Ciphertext],
+    ) -> (Vec<Ciphertext>, ShuffleProof) {
+        let n = previous.len();
+        let base = |scalar: &Scalar| scalar * &RISTRETTO_BASEPOINT_TABLE;
+        let permutation = rng.permutation(n);
+        let masks = random_scalars(rng, n);
+        let new_cards = permutation
+            .iter()
+            .zip(masks.iter())
+            .map(|(&j, mask)| remask(&previous[j as usize], mask, joint_key))
+            .collect::<Vec<_>>();
+        let new_points = decode_cards(&new_cards).unwrap();
+        let generators = commitment_generators(n);
+
+        let r = random_scalars(rng, n);
+        let mut permutation_commitments = vec![EncodedPoint([0; 32]); n];
+        for (i, &j) in permutation.iter().enumerate() {
+            permutation_commitments[j as usize] =
+                EncodedPoint::new(&(base(&r[j as usize]) + generators[i + 1]));
+        }
+
+        let seed = hash_values(
+            &[],
+            iter::once(&EncodedPoint::new(joint_key).0)
+                .chain(card_bytes(previous))
+                .chain(card_bytes(&new_cards))
+                .chain(permutation_commitments.iter().map(|point| &point.0)),
+        );
+        let u = (0..n as u64)
+            .map(|j| derive_challenge(&seed, j))
+            .collect::<Vec<_>>();
+        let v = permutation
+            .iter()
+            .map(|&j| u[j as usize])
+            .collect::<Vec<_>>();
+
+        let s = random_scalars(rng, n);
+        let mut chain = vec![];
+        for i in 0..n {
+            let last = chain.last().unwrap_or(&generators[0]);
+            let link = base(&s[i]) + v[i] * last;
+            chain.push(link);
+        }
+
+        let w = random_scalars(rng, 4);
+        let w_chain = random_scalars(rng, n);
+        let w_permuted = random_scalars(rng, n);
+        let commitments = [
+            base(&w[0]),
+            base(&w[1]),
+            base(&w[2])
+                + generators[1..]
+                    .iter()
+                    .zip(w_permuted.iter())
+                    .map(|(generator, w_i)| w_i * generator)
+                    .sum::<RistrettoPoint>(),
+        ];
+        let deck_commitment = Ciphertext {
+            mask: EncodedPoint::new(
+                &(new_points
+                    .iter()
+                    .zip(w_permuted.iter())
+                    .map(|((mask, _), w_i)| w_i * mask)
+                    .sum::<RistrettoPoint>()
+                    - base(&w[3])),
+            ),
+            value: EncodedPoint::new(
+                &(new_points
+                    .iter()
+                    .zip(w_permuted.iter())
+                    .map(|((_, value), w_i)| w_i * value)
+                    .sum::<RistrettoPoint>()
+                    - w[3] * joint_key),
+            ),
+        };
+        let chain_commitments = (0..n)
+            .map(|i| {
+                let last = if i == 0 {
+                    &generators[0]
+                } else {
+                    &chain[i - 1]
+                };
+                EncodedPoint::new(&(base(&w_chain[i]) + w_permuted[i] * last))
+            })
+            .collect::<Vec<_>>();
+        let chain = chain.iter().map(EncodedPoint::new).collect::<Vec<_>>();
+        let commitments = commitments
+            .iter()
+            .map(EncodedPoint::new)
+            .collect::<Vec<_>>();
+
+        let challenge_hash = hash_values(
+            &seed,
+            chain
+                .iter()
+                .chain(commitments.iter())
+                .map(|point| &point.0)
+                .chain(card_bytes(&[deck_commitment]))
+                .chain(chain_commitments.iter().map(|point| &point.0)),
+        );
+        let c = derive_challenge(&challenge_hash, 0);
+
+        let chain_randomness = s
+            .iter()
+            .zip(v.iter())
+            .fold(Scalar::zero(), |sum, (s_i, v_i)| sum * v_i + s_i);
+        let encode = |scalar: Scalar| EncodedScalar(scalar.to_bytes());
+        let proof = ShuffleProof {
+            permutation_commitments,
+            chain,
+            commitments,
+            deck_commitment,
+            chain_commitments,
+            responses: vec![
+                encode(w[0] + c * r.iter().sum::<Scalar>()),
+                encode(w[1] + c * chain_randomness),
+                encode(
+                    w[2] + c * r
+                        .iter()
+                        .zip(u.iter())
+                        .map(|(r_j, u_j)| r_j * u_j)
+                        .sum::<Scalar>(),
+                ),
+                encode(
+                    w[3] + c * masks
+                        .iter()
+                        .zip(v.iter())
+                        .map(|(m_i, v_i)| m_i * v_i)
+                        .sum::<Scalar>(),
+                ),
+            ],
+            chain_responses: w_chain
+                .iter()
+                .zip(s.iter())
+                .map(|(w_i, s_i)| encode(w_i + c * s_i))
+                .collect(),
+            permuted_responses: w_permuted
+                .iter()
+                .zip(v.iter())
+                .map(|(w_i, v_i)| encode(w_i + c * v_i))
+                .collect(),
+        };
+        (new_cards, proof)
+    }
+
+    fn players(rng: &mut Rng, num_players: usize) -> (Vec<Scalar>, Vec<PublicKey>) {
+        let secret_keys = (0..num_players)
+            .map(|_| random_scalar(rng))
+            .collect::<Vec<_>>();
+        let public_keys = secret_keys
+            .iter()
+            .map(|secret_key| public_key(rng, secret_key))
+            .collect();
+        (secret_keys, public_keys)
+    }
+
+    #[test]
+    fn encoded_cards() {
+        for card in 0..DECK_SIZE {
+            assert_eq!(ENCODED_CARDS[card as usize], encode_card(card).0);
+            let ciphertext = Ciphertext {
+                mask: EncodedPoint::new(&RistrettoPoint::identity()),
+                value: encode_card(card),
+            };
+            assert_eq!(ElGamal::decode_card(&ciphertext, DECK_SIZE), Some(card));
+        }
+    }
+
+    #[test]
+    fn valid_public_key() {
+        set_signer("alice");
+        let mut rng = Rng::new(1);
+        let secret_key = random_scalar(&mut rng);
+        let mut public_key = public_key(&mut rng, &secret_key);
+        assert!(ElGamal::is_valid_public_key(&public_key));
+        assert!(ElGamal::is_secret_key(
+            &public_key,
+            &EncodedScalar(secret_key.to_bytes())
+        ));
+
+        public_key.response = EncodedScalar(random_scalar(&mut rng).to_bytes());
+        assert!(!ElGamal::is_valid_public_key(&public_key));
+    }
+
+    #[test]
+    fn verify_valid_shuffle() {
+        set_signer("alice");
+        let mut rng = Rng::new(2);
+        let (secret_keys, public_keys) = players(&mut rng, 3);
+        let previous = ElGamal::initial_cards(52);
+        let (new_cards, proof) = shuffle(&mut rng, &joint_key(&public_keys).unwrap(), &previous);
+
+        assert!(verify_shuffle(&public_keys, &previous, &new_cards, &proof));
+        assert!(!verify_shuffle(
+            &public_keys[..2],
+            &previous,
+            &new_cards,
+            &proof
+        ));
+
+        let secret_keys = secret_keys
+            .iter()
+            .map(|secret_key| Some(EncodedScalar(secret_key.to_bytes())))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ElGamal::replay_shuffle(&secret_keys, 0, &previous, &new_cards),
+            Some(true)
+        );
+        assert_eq!(
+            ElGamal::replay_shuffle(&[secret_keys[0], None], 0, &previous, &new_cards),
+            None
+        );
+    }
+
+    #[test]
+    fn reject_modified_shuffle() {
+        set_signer("alice");
+        let mut rng = Rng::new(3);
+        let (secret_keys, public_keys) = players(&mut rng, 2);
+        let previous = ElGamal::initial_cards(52);
+        let joint_key = joint_key(&public_keys).unwrap();
+        let (mut new_cards, proof) = shuffle(&mut rng, &joint_key, &previous);
+        new_cards[0] = remask(&new_cards[1], &random_scalar(&mut rng), &joint_key);
+
+        assert!(!verify_shuffle(&public_keys, &previous, &new_cards, &proof));
+
+        let secret_keys = secret_keys
+            .iter()
+            .map(|secret_key| Some(EncodedScalar(secret_key.to_bytes())))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ElGamal::replay_shuffle(&secret_keys, 0, &previous, &new_cards),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn reject_modified_proof() {
+        set_signer("alice");
+        let mut rng = Rng::new(6);
+        let (_, public_keys) = players(&mut rng, 3);
+        let previous = ElGamal::initial_cards(52);
+        let (new_cards, proof) = shuffle(&mut rng, &joint_key(&public_keys).unwrap(), &previous);
+        assert!(verify_shuffle(&public_keys, &previous, &new_cards, &proof));
+
+        fn add_one(scalar: &EncodedScalar) -> EncodedScalar {
+            EncodedScalar((scalar.decode().unwrap() + Scalar::one()).to_bytes())
+        }
+        let modifications: Vec<fn(&mut ShuffleProof)> = vec![
+            |proof| proof.responses[3] = add_one(&proof.responses[3]),
+            |proof| proof.chain_responses[7] = add_one(&proof.chain_responses[7]),
+            |proof| proof.permuted_responses[51] = add_one(&proof.permuted_responses[51]),
+            |proof| proof.chain.swap(3, 4),
+            |proof| proof.permutation_commitments.swap(0, 1),
+            |proof| {
+                let deck_commitment = &mut proof.deck_commitment;
+                std::mem::swap(&mut deck_commitment.mask, &mut deck_commitment.value)
+            },
+            |proof| proof.responses[0] = EncodedScalar([0xff; 32]),
+            |proof| {
+                proof.commitments.pop();
+            },
+        ];
+        for modify in modifications {
+            let mut modified = proof.clone();
+            modify(&mut modified);
+            assert!(!verify_shuffle(
+                &public_keys,
+                &previous,
+                &new_cards,
+                &modified
+            ));
+        }
+    }
+
+    #[test]
+    fn reveal_card() {
+        set_signer("alice");
+        let mut rng = Rng::new(4);
+        let (secret_keys, public_keys) = players(&mut rng, 2);
+        let previous = ElGamal::initial_cards(52);
+        let (cards, _) = shuffle(&mut rng, &joint_key(&public_keys).unwrap(), &previous);
+
+        let mut progress = cards[0];
+        for (secret_key, public_key) in secret_keys.iter().zip(public_keys.iter()) {
+            let (mask, _) = progress.decode().unwrap();
+            let share = EncodedPoint::new(&(secret_key * mask));
+            let w = random_scalar(&mut rng);
+            let base_commitment = EncodedPoint::new(&(&w * &RISTRETTO_BASEPOINT_TABLE));
+            let card_commitment = EncodedPoint::new(&(w * mask));
+            let challenge = challenge_scalar(&[
+                EncodedPoint::new(&RISTRETTO_BASEPOINT_POINT),
+                public_key.key,
+                progress.mask,
+                share,
+                base_commitment,
+                card_commitment,
+            ]);
+            let proof = RevealProof {
+                base_commitment,
+                card_commitment,
+                response: EncodedScalar((w + challenge * secret_key).to_bytes()),
+            };
+
+            let other_key = &public_keys[if public_key.key == public_keys[0].key {
+                1
+            } else {
+                0
+            }];
+            assert!(ElGamal::reveal(other_key, &progress, &share, &proof).is_none());
+            assert!(ElGamal::replay_reveal(
+                &EncodedScalar(secret_key.to_bytes()),
+                &progress,
+                &share
+            ));
+            progress = ElGamal::reveal(public_key, &progress, &share, &proof).unwrap();
+        }
+
+        assert!(ElGamal::decode_card(&progress, 52).is_some());
+    }
+
+    /// Time to verify a shuffle of a full deck. Run with
+    /// `cargo test --release -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_verify_shuffle() {
+        set_signer("alice");
+        let mut rng = Rng::new(5);
+        let (_, public_keys) = players(&mut rng, 6);
+        let previous = ElGamal::initial_cards(52);
+        let (new_cards, proof) = shuffle(&mut rng, &joint_key(&public_keys).unwrap(), &previous);
+
+        let start = Instant::now();
+        assert!(verify_shuffle(&public_keys, &previous, &new_cards, &proof));
+        println!(
+            "ElGamal shuffle of 52 cards verified in {:?}",
+            start.elapsed()
+        );
+    }
+}
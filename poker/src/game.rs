@@ -1,17 +1,69 @@
-use crate::deck::{Deck, DeckError, DeckStatus};
-use crate::poker::{ActionResponse, BetAction, Poker, PokerError, PokerStatus};
-use crate::types::{CryptoHash, PlayerId, RoomId};
+use crate::config::{ConfigError, TableConfig};
+use crate::deck::{AnyDeck, AuditResult, DeckError};
+use crate::hand::DECK_SIZE;
+use crate::poker::{
+    ActionResponse, BetAction, BlindLevelInfo, LegalActions, Poker, PokerError, PokerStatus,
+    ShowdownAction,
+};
+use crate::pot::Settlement;
+use crate::scheme::{Cards, PublicKey, RevealPart, RevealProof, SecretKey, ShuffleProof};
+use crate::types::{to_hex, AccountId, Balance, Gas, PlayerId, RoomId};
 use borsh::{BorshDeserialize, BorshSerialize};
-use serde::Serialize;
+use near_bindgen::{env, Promise};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+
+/// Gas attached to `ft_transfer` calls on the token contract.
+const GAS_FOR_FT_TRANSFER: Gas = 10_000_000_000_000;
+
+/// Gas attached to the callbacks that check the result of a payout.
+const GAS_FOR_PAYOUT_CALLBACK: Gas = 10_000_000_000_000;
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Debug)]
 pub enum GameError {
     RoomIdNotFound,
     OngoingRound,
+    /// Tried to enter a room that already has the maximum number of players.
+    RoomFull,
+    /// Tried to start a game without the minimum number of players.
+    NotEnoughPlayers,
+    /// Tried to start a game, but some players are not ready.
+    PlayersNotReady,
+    /// Account is not a player of this room.
+    NotAPlayer,
+    /// Only the host of the room can do this action.
+    NotHost,
+    /// Tried to start a hand while the room is paused by the host.
+    RoomPaused,
+    /// Password provided to enter a private room is missing or wrong.
+    InvalidPassword,
+    /// Tried to enter a private room without being invited by the host.
+    NotInvited,
+    /// Deposit attached to enter is not a whole number of tokens within the buy-in
+    /// limits of the room.
+    InvalidBuyIn,
+    /// Tried to buy tokens with a currency different from the one used by the room.
+    InvalidCurrency,
+    /// Tried to withdraw, but there are no failed payouts for this player.
+    NothingToWithdraw,
+    /// Tried to claim a timeout, but no player is expected to act or the deadline
+    /// is not over yet.
+    DeadlineNotReached,
+    /// Tried to get ready, leave or close the room without revealing the secret key used
+    /// in the last hand.
+    SecretKeyNotRevealed,
+    ConfigError(ConfigError),
     DeckError(DeckError),
     PokerError(PokerError),
 }
 
+impl From<ConfigError> for GameError {
+    fn from(config_error: ConfigError) -> Self {
+        GameError::ConfigError(config_error)
+    }
+}
+
 impl From<DeckError> for GameError {
     fn from(deck_error: DeckError) -> Self {
         GameError::DeckError(deck_error)
@@ -24,6 +76,14 @@ impl From<PokerError> for GameError {
     }
 }
 
+/// Status of the game and which players are ready to start the next hand.
+#[derive(Serialize)]
+pub struct GameState {
+    pub status: GameStatus,
+    /// Whether each player is ready to start the next hand.
+    pub ready: Vec<bool>,
+}
+
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Eq, PartialEq, Clone)]
 pub enum GameStatus {
     // Start haven't been called. Players are able to enter the game.
@@ -48,50 +108,324 @@ impl GameStatus {
     }
 }
 
-// TODO: Use NEAR Tokens
+/// Who can enter a room. Private rooms are not listed to accounts that are not invited.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+pub enum Access {
+    /// Anyone can enter.
+    Public,
+    /// Players should provide a password `p` such that the hex encoded sha256 of
+    /// `salt + p` is `hash`.
+    Password { salt: String, hash: String },
+    /// Only these accounts can enter.
+    Invite(Vec<AccountId>),
+}
+
+impl Default for Access {
+    fn default() -> Self {
+        Access::Public
+    }
+}
+
+/// Pots and payouts of a round. Player ids in the settlement are positions in `players`,
+/// which are the players seated when the round was played.
+#[derive(Serialize)]
+pub struct RoundResult {
+    pub players: Vec<AccountId>,
+    pub settlement: Settlement,
+}
+
+/// Player the game is waiting on, and when its action clock runs out.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone, Debug)]
+pub struct Deadline {
+    pub player_id: PlayerId,
+    /// Block index or timestamp, depending on `config.action_timeout`. After it, the time
+    /// bank of the player is consumed.
+    pub at: u64,
+}
+
 #[derive(BorshDeserialize, BorshSerialize, Serialize)]
 pub struct Game {
     pub name: String,
     pub id: RoomId,
     pub status: GameStatus,
-    deck: Deck,
+    pub config: TableConfig,
+    /// Account that created the room. It can close, pause and resume the room, and kick players.
+    pub host: AccountId,
+    /// No new hands are started while the room is paused.
+    pub paused: bool,
+    /// Who can enter the room. Set by the host.
+    access: Access,
+    /// Players that are ready to start the next hand.
+    ready: Vec<bool>,
+    /// Players that lost all their tokens, in the order they were eliminated.
+    pub eliminated: Vec<AccountId>,
+    /// Player that won all the tokens. The game is closed once there is a winner.
+    pub winner: Option<AccountId>,
+    /// Deadline for the player in turn, or for players that should reveal the secret key
+    /// used in the last hand. None if no player is expected to act.
+    deadline: Option<Deadline>,
+    /// Players slashed for stalling the game.
+    pub slashed: Vec<AccountId>,
+    /// Players caught cheating when auditing a hand, or that left it without revealing
+    /// their secret key before the next hand was over.
+    pub cheaters: Vec<AccountId>,
+    /// Payouts that failed. They can be withdrawn again.
+    failed_payouts: HashMap<AccountId, Balance>,
+    /// Players seated on the last round played.
+    last_round_players: Vec<AccountId>,
+    deck: AnyDeck,
     poker: Poker,
 }
 
 impl Game {
-    pub fn new(name: String, id: RoomId) -> Self {
-        Self {
+    pub fn new(
+        name: String,
+        id: RoomId,
+        config: TableConfig,
+        host: AccountId,
+        access: Access,
+    ) -> Result<Self, GameError> {
+        config.validate()?;
+
+        Ok(Self {
             name,
             id,
             status: GameStatus::Initiating,
-            deck: Deck::new(52),
-            poker: Poker::new(),
-        }
+            host,
+            paused: false,
+            access,
+            ready: vec![],
+            eliminated: vec![],
+            winner: None,
+            last_round_players: vec![],
+            deadline: None,
+            slashed: vec![],
+            cheaters: vec![],
+            failed_payouts: HashMap::new(),
+            deck: AnyDeck::new(config.scheme, DECK_SIZE),
+            poker: Poker::new(&config),
+            config,
+        })
     }
 
-    pub fn enter(&mut self) -> Result<(), GameError> {
-        self.deck.enter().map_err(Into::<GameError>::into)?;
-        // TODO: Put min tokens / max tokens caps
-        self.poker.new_player(1000);
+    /// Enter the room as `account_id` buying tokens with `deposit`, paid with the fungible
+    /// token `token_account_id`, or NEAR if it is None. The deposit is held by the contract
+    /// until the player is paid back. `public_key` is used to check the cards submitted by
+    /// the player. `password` is only checked on rooms protected with a password.
+    pub fn enter(
+        &mut self,
+        account_id: AccountId,
+        public_key: PublicKey,
+        token_account_id: Option<&AccountId>,
+        deposit: Balance,
+        password: Option<String>,
+    ) -> Result<(), GameError> {
+        self.check_access(&account_id, password)?;
+
+        if token_account_id != self.config.currency.token_account_id() {
+            return Err(GameError::InvalidCurrency);
+        }
+
+        if self.deck.num_players() >= self.config.max_players {
+            return Err(GameError::RoomFull);
+        }
+
+        if deposit % self.config.chip_value != 0 {
+            return Err(GameError::InvalidBuyIn);
+        }
+
+        let tokens = deposit / self.config.chip_value;
+        if tokens < self.config.min_buy_in.into() || tokens > self.config.max_buy_in.into() {
+            return Err(GameError::InvalidBuyIn);
+        }
+
+        self.deck
+            .enter(account_id, public_key)
+            .map_err(Into::<GameError>::into)?;
+        self.poker.new_player(tokens as u64);
+        self.ready.push(false);
         Ok(())
     }
 
-    // TODO: An idle game should be started by all players.
-    pub fn start(&mut self) -> Result<(), GameError> {
+    /// Position of `account_id` in the room.
+    fn find_player(&self, account_id: &AccountId) -> Result<PlayerId, GameError> {
+        self.deck
+            .get_players()
+            .iter()
+            .position(|player| player == account_id)
+            .map(|player_id| player_id as PlayerId)
+            .ok_or(GameError::NotAPlayer)
+    }
+
+    /// Mark `account_id` as ready to start the next hand. If every player is ready and
+    /// the room is configured to start automatically, the hand is started. Players should
+    /// reveal the secret key used in the last hand before getting ready.
+    pub fn ready(&mut self, account_id: AccountId) -> Result<(), GameError> {
         match self.status {
             GameStatus::Initiating | GameStatus::Idle => {
+                let player_id = self.find_player(&account_id)?;
+                if self.deck.is_secret_key_pending(&account_id) {
+                    return Err(GameError::SecretKeyNotRevealed);
+                }
+                self.ready[player_id as usize] = true;
+                self.auto_start()
+            }
+            _ => Err(GameError::OngoingRound),
+        }
+    }
+
+    /// Start the hand if the room is configured to start automatically and it is possible.
+    fn auto_start(&mut self) -> Result<(), GameError> {
+        if self.config.auto_start
+            && !self.paused
+            && self.ready.iter().all(|&ready| ready)
+            && self.deck.num_players() >= self.config.min_players
+        {
+            self.start_hand()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Start the next hand. It can be called by any player once every player is ready.
+    pub fn start(&mut self, account_id: AccountId) -> Result<(), GameError> {
+        self.find_player(&account_id)?;
+        self.start_hand()
+    }
+
+    fn start_hand(&mut self) -> Result<(), GameError> {
+        match self.status {
+            GameStatus::Initiating | GameStatus::Idle => {
+                if self.paused {
+                    return Err(GameError::RoomPaused);
+                }
+
+                if self.deck.num_players() < self.config.min_players {
+                    return Err(GameError::NotEnoughPlayers);
+                }
+
+                if !self.ready.iter().all(|&ready| ready) {
+                    return Err(GameError::PlayersNotReady);
+                }
+
                 self.deck.start().map_err(Into::<GameError>::into)?;
+                self.ready = vec![false; self.ready.len()];
+                self.poker.update_blind_level(env::block_timestamp());
                 self.status = GameStatus::DeckAction;
+                self.update_deadline();
                 Ok(())
             }
             _ => Err(GameError::OngoingRound),
         }
     }
 
-    pub fn close(&mut self) -> Result<(), GameError> {
+    fn check_access(
+        &self,
+        account_id: &AccountId,
+        password: Option<String>,
+    ) -> Result<(), GameError> {
+        match &self.access {
+            Access::Public => Ok(()),
+            Access::Password { salt, hash } => {
+                let password = password.ok_or(GameError::InvalidPassword)?;
+                let salted = format!("{}{}", salt, password);
+                if to_hex(&env::sha256(salted.as_bytes())) == hash.to_lowercase() {
+                    Ok(())
+                } else {
+                    Err(GameError::InvalidPassword)
+                }
+            }
+            Access::Invite(accounts) => {
+                if accounts.contains(account_id) || *account_id == self.host {
+                    Ok(())
+                } else {
+                    Err(GameError::NotInvited)
+                }
+            }
+        }
+    }
+
+    pub fn is_private(&self) -> bool {
+        !matches!(self.access, Access::Public)
+    }
+
+    /// Whether the room is listed to `account_id`. Private rooms are only listed to the
+    /// host, its players and invited accounts.
+    pub fn is_visible_to(&self, account_id: Option<&AccountId>) -> bool {
+        match (&self.access, account_id) {
+            (Access::Public, _) => true,
+            (_, None) => false,
+            (access, Some(account_id)) => {
+                *account_id == self.host
+                    || self.find_player(account_id).is_ok()
+                    || match access {
+                        Access::Invite(accounts) => accounts.contains(account_id),
+                        _ => false,
+                    }
+            }
+        }
+    }
+
+    /// Change who can enter the room. Players already seated are not affected.
+    pub fn set_access(&mut self, account_id: AccountId, access: Access) -> Result<(), GameError> {
+        self.check_host(&account_id)?;
+        self.access = access;
+        Ok(())
+    }
+
+    fn check_host(&self, account_id: &AccountId) -> Result<(), GameError> {
+        if *account_id == self.host {
+            Ok(())
+        } else {
+            Err(GameError::NotHost)
+        }
+    }
+
+    /// Stop starting new hands. The current hand, if any, is played until the end.
+    pub fn pause(&mut self, account_id: AccountId) -> Result<(), GameError> {
+        self.check_host(&account_id)?;
+        self.paused = true;
+        Ok(())
+    }
+
+    pub fn resume(&mut self, account_id: AccountId) -> Result<(), GameError> {
+        self.check_host(&account_id)?;
+        self.paused = false;
+        self.auto_start()
+    }
+
+    /// Remove `player` from the room between hands paying back the value of its tokens.
+    /// Players that didn't reveal the secret key used in the last hand can't be kicked,
+    /// they are slashed once the timeout is claimed.
+    pub fn kick(&mut self, account_id: AccountId, player: AccountId) -> Result<(), GameError> {
+        self.check_host(&account_id)?;
+        self.leave(player)
+    }
+
+    /// Make `new_host`, which should be a player, the host of the room.
+    pub fn transfer_host(
+        &mut self,
+        account_id: AccountId,
+        new_host: AccountId,
+    ) -> Result<(), GameError> {
+        self.check_host(&account_id)?;
+        self.find_player(&new_host)?;
+        self.host = new_host;
+        Ok(())
+    }
+
+    /// Close the room paying back every player. Only allowed between rounds, once every
+    /// player revealed the secret key used in the last hand.
+    pub fn close(&mut self, account_id: AccountId) -> Result<(), GameError> {
+        self.check_host(&account_id)?;
+
         match self.status {
             GameStatus::Initiating | GameStatus::Idle => {
-                self.deck.close();
+                if self.pending_secret_key().is_some() {
+                    return Err(GameError::SecretKeyNotRevealed);
+                }
+                self.close_deck();
+                self.pay_out();
                 self.status = GameStatus::Closed;
                 Ok(())
             }
@@ -99,12 +433,162 @@ impl Game {
         }
     }
 
+    /// Leave the room paying back to `account_id` the value of its tokens. Only allowed
+    /// between rounds, after revealing the secret key used in the last hand. Players after
+    /// it move one seat down.
+    pub fn leave(&mut self, account_id: AccountId) -> Result<(), GameError> {
+        match self.status {
+            GameStatus::Initiating | GameStatus::Idle => {
+                let player_id = self.find_player(&account_id)?;
+                if self.deck.is_secret_key_pending(&account_id) {
+                    return Err(GameError::SecretKeyNotRevealed);
+                }
+
+                let tokens = self.poker.cash_out(player_id);
+                if tokens > 0 {
+                    self.transfer(account_id, Balance::from(tokens) * self.config.chip_value);
+                }
+
+                self.remove_player(player_id);
+
+                if self.status == GameStatus::Idle {
+                    self.status = self.check_winner();
+                }
+                // Players after it moved one seat down.
+                self.next_reveal_deadline();
+                Ok(())
+            }
+            _ => Err(GameError::OngoingRound),
+        }
+    }
+
+    /// Remove `player_id` from the deck and the table. Should only be called between rounds.
+    fn remove_player(&mut self, player_id: PlayerId) -> AccountId {
+        let account_id = self
+            .deck
+            .remove_player(player_id)
+            .expect("Players can only be removed between rounds.");
+        self.poker.remove_player(player_id);
+        self.ready.remove(player_id as usize);
+        account_id
+    }
+
+    /// Transfer back to every player the value of its tokens.
+    fn pay_out(&mut self) {
+        for (player_id, account_id) in self.deck.get_players().into_iter().enumerate() {
+            let tokens = self.poker.cash_out(player_id as PlayerId);
+            if tokens > 0 {
+                self.transfer(account_id, Balance::from(tokens) * self.config.chip_value);
+            }
+        }
+    }
+
+    /// Send `amount` of the currency of the room to `account_id`. Transfers are checked on
+    /// `on_near_payout` or `on_ft_payout`, and restored as failed payouts if they don't
+    /// succeed.
+    fn transfer(&self, account_id: AccountId, amount: Balance) {
+        let callback_args = json!({
+            "room_id": self.id,
+            "account_id": account_id,
+            "amount": amount.to_string(),
+        });
+
+        match self.config.currency.token_account_id() {
+            None => {
+                Promise::new(account_id).transfer(amount).then(
+                    Promise::new(env::current_account_id()).function_call(
+                        b"on_near_payout".to_vec(),
+                        callback_args.to_string().into_bytes(),
+                        0,
+                        GAS_FOR_PAYOUT_CALLBACK,
+                    ),
+                );
+            }
+            Some(token_account_id) => {
+                let transfer_args = json!({
+                    "receiver_id": account_id,
+                    "amount": amount.to_string(),
+                });
+
+                // NEP-141 requires attaching exactly one yoctoNEAR to `ft_transfer`.
+                Promise::new(token_account_id.clone())
+                    .function_call(
+                        b"ft_transfer".to_vec(),
+                        transfer_args.to_string().into_bytes(),
+                        1,
+                        GAS_FOR_FT_TRANSFER,
+                    )
+                    .then(Promise::new(env::current_account_id()).function_call(
+                        b"on_ft_payout".to_vec(),
+                        callback_args.to_string().into_bytes(),
+                        0,
+                        GAS_FOR_PAYOUT_CALLBACK,
+                    ));
+            }
+        }
+    }
+
+    /// Payout of `amount` to `account_id` failed. Keep it so it can be withdrawn again.
+    pub fn payout_failed(&mut self, account_id: AccountId, amount: Balance) {
+        *self.failed_payouts.entry(account_id).or_insert(0) += amount;
+    }
+
+    /// Retry all failed payouts to `account_id`.
+    pub fn withdraw(&mut self, account_id: AccountId) -> Result<(), GameError> {
+        let amount = self
+            .failed_payouts
+            .remove(&account_id)
+            .ok_or(GameError::NothingToWithdraw)?;
+        self.transfer(account_id, amount);
+        Ok(())
+    }
+
+    /// Value of the tokens held by the contract on behalf of `account_id` in this room,
+    /// including failed payouts.
+    pub fn escrowed_balance(&self, account_id: &AccountId) -> Balance {
+        let in_game = self.find_player(account_id).map_or(0, |player_id| {
+            Balance::from(self.poker.tokens(player_id)) * self.config.chip_value
+        });
+
+        in_game + self.failed_payouts.get(account_id).cloned().unwrap_or(0)
+    }
+
+    /// Remove players without tokens after a round is over.
+    fn finish_round(&mut self) -> GameStatus {
+        self.close_deck();
+        self.last_round_players = self.deck.get_players();
+
+        // Remove from the last seat, so ids of busted players are still valid.
+        for player_id in self.poker.busted_players().into_iter().rev() {
+            let account_id = self.remove_player(player_id);
+            self.eliminated.push(account_id);
+        }
+
+        self.check_winner()
+    }
+
+    /// Close the deck. Players that never revealed the secret key of the previous hand
+    /// can't be audited anymore, so they are recorded as cheaters.
+    fn close_deck(&mut self) {
+        let withheld = self.deck.close();
+        self.cheaters.extend(withheld);
+    }
+
+    /// If only one player is left after the game started, it is the winner and the game
+    /// is closed.
+    fn check_winner(&mut self) -> GameStatus {
+        if self.deck.num_players() == 1 {
+            self.winner = self.deck.get_players().pop();
+            self.pay_out();
+            GameStatus::Closed
+        } else {
+            GameStatus::Idle
+        }
+    }
+
     fn check_status(&mut self) {
         self.status = match self.poker.status {
-            PokerStatus::Idle => {
-                self.deck.close();
-                GameStatus::Idle
-            }
+            PokerStatus::Idle => self.finish_round(),
             PokerStatus::Dealing {
                 player_id, card_id, ..
             } => {
@@ -118,7 +602,9 @@ impl Game {
 
                 GameStatus::DeckAction
             }
-            PokerStatus::Betting { .. } => GameStatus::PokerAction,
+            PokerStatus::StraddleDecision { .. }
+            | PokerStatus::Betting { .. }
+            | PokerStatus::ShowdownDecision { .. } => GameStatus::PokerAction,
             PokerStatus::Revealing { card_id, .. } | PokerStatus::Showdown { card_id, .. } => {
                 self.deck.reveal_card(card_id, None).expect(
                     format!("Impossible to reveal card {} for the table.", card_id).as_ref(),
@@ -126,19 +612,18 @@ impl Game {
                 GameStatus::DeckAction
             }
             PokerStatus::WaitingRevealedCards => {
-                self.poker.submit_revealed_cards(self.deck.revealed.clone());
-                self.deck.close();
-                GameStatus::Idle
+                self.poker.submit_revealed_cards(self.deck.revealed_cards());
+                self.finish_round()
             }
         };
+        self.update_deadline();
     }
 
     /// Deck finalized one step.
     fn check_next_status(&mut self) {
-        let deck_status = self.deck.get_status();
-
-        if deck_status != DeckStatus::Running {
+        if !self.deck.is_running() {
             self.status = GameStatus::DeckAction;
+            self.update_deadline();
             return;
         }
 
@@ -146,7 +631,7 @@ impl Game {
         self.check_status();
     }
 
-    pub fn deck_state(&self) -> Deck {
+    pub fn deck_state(&self) -> AnyDeck {
         self.deck.clone()
     }
 
@@ -154,8 +639,22 @@ impl Game {
         self.poker.clone()
     }
 
-    pub fn state(&self) -> GameStatus {
-        self.status.clone()
+    /// Status of the game and which players are ready to start the next hand.
+    pub fn state(&self) -> GameState {
+        GameState {
+            status: self.status.clone(),
+            ready: self.ready.clone(),
+        }
+    }
+
+    /// Pots and payouts from the last round played.
+    pub fn last_settlement(&self) -> Option<RoundResult> {
+        self.poker
+            .get_last_settlement()
+            .map(|settlement| RoundResult {
+                players: self.last_round_players.clone(),
+                settlement,
+            })
     }
 
     pub fn player_id(&self) -> Result<PlayerId, GameError> {
@@ -171,21 +670,149 @@ impl Game {
         }
     }
 
-    // TODO: Mechanism to slash participants that are stalling the game
-    //       Discussion: Using some number of epochs, elapsed without inactivity.
+    /// Blinds currently used and blinds used after current level is over.
+    pub fn blind_level(&self) -> BlindLevelInfo {
+        self.poker.blind_level_info()
+    }
+
+    /// Actions available to the player that should bet.
+    pub fn legal_actions(&self) -> Option<LegalActions> {
+        match self.status {
+            GameStatus::PokerAction => self.poker.legal_actions(),
+            _ => None,
+        }
+    }
+
+    /// Start the time the player in turn has to make the next action. Between hands, start
+    /// the time players have to reveal the secret key used in the last hand.
+    fn update_deadline(&mut self) {
+        self.deadline = self
+            .get_turn()
+            .or_else(|| self.pending_secret_key())
+            .map(|player_id| Deadline {
+                player_id,
+                at: self.config.action_timeout.deadline(),
+            });
+    }
+
+    /// First seated player that should reveal the secret key used in the last hand before
+    /// the next hand starts.
+    fn pending_secret_key(&self) -> Option<PlayerId> {
+        if self.status != GameStatus::Idle {
+            return None;
+        }
+
+        self.deck
+            .get_players()
+            .iter()
+            .position(|account_id| self.deck.is_secret_key_pending(account_id))
+            .map(|player_id| player_id as PlayerId)
+    }
+
+    /// Wait on the next player that should reveal its secret key. Every player reveals at
+    /// the same time, so the deadline is kept.
+    fn next_reveal_deadline(&mut self) {
+        if let Some(at) = self.deadline.as_ref().map(|deadline| deadline.at) {
+            self.deadline = self
+                .pending_secret_key()
+                .map(|player_id| Deadline { player_id, at });
+        }
+    }
+
+    /// Consume the time bank of the player in turn if it acted after its action clock
+    /// ran out. Should be called after each action, before the turn changes.
+    fn use_time_bank(&mut self) {
+        if let Some(deadline) = &self.deadline {
+            let overtime = self.config.action_timeout.now().saturating_sub(deadline.at);
+            self.poker.use_time_bank(deadline.player_id, overtime);
+        }
+    }
+
+    /// Deadline for the player in turn. None if no player is expected to act.
+    pub fn deadline(&self) -> Option<Deadline> {
+        self.deadline.clone()
+    }
+
+    /// Unblock a game where the player in turn didn't act before the deadline and used
+    /// all its time bank. On betting rounds the player folds, at showdown it mucks its
+    /// cards, and it doesn't straddle if it was deciding whether to straddle. If the player
+    /// should act on the deck the round can't continue: the round is cancelled, its tokens
+    /// are split between the other players and it is removed from the room. A player that
+    /// doesn't reveal the secret key used in the last hand is slashed and removed in the
+    /// same way.
+    pub fn claim_timeout(&mut self) -> Result<(), GameError> {
+        let player_id = match &self.deadline {
+            Some(deadline)
+                if self.config.action_timeout.now()
+                    > deadline
+                        .at
+                        .saturating_add(self.poker.time_bank(deadline.player_id)) =>
+            {
+                deadline.player_id
+            }
+            _ => return Err(GameError::DeadlineNotReached),
+        };
+        self.poker.use_time_bank(player_id, u64::MAX);
+
+        match self.status {
+            GameStatus::PokerAction => {
+                match self.poker.get_status() {
+                    PokerStatus::ShowdownDecision { .. } => self
+                        .poker
+                        .submit_showdown_action(player_id, ShowdownAction::Muck)?,
+                    PokerStatus::StraddleDecision { .. } => {
+                        self.poker.submit_straddle(player_id, false)?
+                    }
+                    _ => self.poker.submit_bet_action(ActionResponse {
+                        player_id,
+                        action: BetAction::Fold,
+                    })?,
+                }
+                self.check_status();
+            }
+            GameStatus::DeckAction => {
+                self.close_deck();
+                self.poker.abort_round();
+                self.poker.slash(player_id);
+
+                let account_id = self.remove_player(player_id);
+                self.slashed.push(account_id);
+
+                self.status = self.check_winner();
+                self.update_deadline();
+            }
+            GameStatus::Idle => {
+                self.poker.slash(player_id);
+
+                let account_id = self.remove_player(player_id);
+                self.slashed.push(account_id);
+
+                self.status = self.check_winner();
+                self.next_reveal_deadline();
+            }
+            _ => return Err(GameError::DeadlineNotReached),
+        }
+
+        Ok(())
+    }
 }
 
 // Implement Deck public interface for Game
 impl Game {
-    pub fn get_partial_shuffle(&self) -> Result<Vec<CryptoHash>, GameError> {
+    pub fn get_partial_shuffle(&self) -> Result<Cards, GameError> {
         self.deck.get_partial_shuffle().map_err(Into::into)
     }
 
-    pub fn submit_shuffled(&mut self, new_cards: Vec<CryptoHash>) -> Result<(), GameError> {
+    pub fn submit_shuffled(
+        &mut self,
+        new_cards: Cards,
+        proof: ShuffleProof,
+    ) -> Result<(), GameError> {
         self.deck
-            .submit_shuffled(new_cards)
+            .submit_shuffled(new_cards, proof)
             .map_err(Into::<GameError>::into)?;
 
+        self.use_time_bank();
         self.check_next_status();
         Ok(())
     }
@@ -193,21 +820,50 @@ impl Game {
     pub fn finish_reveal(&mut self) -> Result<(), GameError> {
         self.deck.finish_reveal().map_err(Into::<GameError>::into)?;
 
+        self.use_time_bank();
         self.check_next_status();
         Ok(())
     }
 
-    pub fn submit_reveal_part(&mut self, card: CryptoHash) -> Result<(), GameError> {
+    pub fn submit_reveal_part(
+        &mut self,
+        part: RevealPart,
+        proof: RevealProof,
+    ) -> Result<(), GameError> {
         self.deck
-            .submit_reveal_part(card)
+            .submit_reveal_part(part, proof)
             .map_err(Into::<GameError>::into)?;
 
+        self.use_time_bank();
         self.check_next_status();
         Ok(())
     }
+
+    /// Reveal the secret key used in the last hand, registering `public_key` for the next
+    /// hands if the player is still seated. Submissions that can be checked with the keys
+    /// revealed so far are replayed, and the first player with a submission that doesn't
+    /// match its key is recorded as a cheater.
+    pub fn submit_secret_key(
+        &mut self,
+        secret_key: SecretKey,
+        public_key: Option<PublicKey>,
+    ) -> Result<(), GameError> {
+        let result = self
+            .deck
+            .submit_secret_key(secret_key, public_key)
+            .map_err(Into::<GameError>::into)?;
+
+        if let Some(AuditResult::Cheated { account_id, .. }) = result {
+            self.cheaters.push(account_id);
+        }
+
+        if self.status == GameStatus::Idle {
+            self.next_reveal_deadline();
+        }
+        Ok(())
+    }
 }
 
-// TODO: On Initiating/Idle games allow leaving and claiming back all winned tokens.
 // Implement Poker public interface for Game
 impl Game {
     pub fn submit_bet_action(&mut self, bet: BetAction) -> Result<(), GameError> {
@@ -218,7 +874,709 @@ impl Game {
             })
             .map_err(Into::<GameError>::into)?;
 
+        self.use_time_bank();
         self.check_status();
         Ok(())
     }
+
+    pub fn submit_straddle(&mut self, straddle: bool) -> Result<(), GameError> {
+        self.poker
+            .submit_straddle(self.player_id()?, straddle)
+            .map_err(Into::<GameError>::into)?;
+
+        self.use_time_bank();
+        self.check_status();
+        Ok(())
+    }
+
+    pub fn submit_showdown_action(&mut self, action: ShowdownAction) -> Result<(), GameError> {
+        self.poker
+            .submit_showdown_action(self.player_id()?, action)
+            .map_err(Into::<GameError>::into)?;
+
+        self.use_time_bank();
+        self.check_status();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::bigint::U256;
+    use crate::config::{TimeBank, Timeout};
+    use crate::deck::DeckStatus;
+    use crate::scheme::CardScheme;
+    use crate::sra::{self, GENERATOR};
+    use crate::test_utils::{get_context, set_signer_at, Rng};
+
+    /// Table with SRA cards, which are cheaper to prove, and actions timed in blocks.
+    pub fn config() -> TableConfig {
+        TableConfig {
+            scheme: CardScheme::Sra,
+            action_timeout: Timeout::Blocks(10),
+            ..Default::default()
+        }
+    }
+
+    /// Room where the secret key of every player is known, so that hands can be played.
+    pub struct Table {
+        pub game: Game,
+        /// Block index of the calls.
+        pub block: u64,
+        keys: HashMap<AccountId, U256>,
+        rng: Rng,
+    }
+
+    impl Table {
+        /// Room hosted by the first of `players`, where every player bought `tokens`.
+        pub fn new(config: TableConfig, players: &[&str], tokens: u64) -> Self {
+            set_signer_at(players[0], 0);
+            let game = Game::new(
+                "room".to_string(),
+                0,
+                config,
+                players[0].to_string(),
+                Access::Public,
+            )
+            .unwrap();
+            let mut table = Table {
+                game,
+                block: 0,
+                keys: HashMap::new(),
+                rng: Rng::new(1),
+            };
+            for player in players {
+                table.enter(player, tokens).unwrap();
+            }
+            table
+        }
+
+        /// Set up the mocked blockchain with `account_id` as signer at the current block.
+        pub fn act_as(&self, account_id: &str) {
+            set_signer_at(account_id, self.block);
+        }
+
+        pub fn enter(&mut self, account_id: &str, tokens: u64) -> Result<(), GameError> {
+            self.enter_with_password(account_id, tokens, None)
+        }
+
+        pub fn enter_with_password(
+            &mut self,
+            account_id: &str,
+            tokens: u64,
+            password: Option<&str>,
+        ) -> Result<(), GameError> {
+            self.act_as(account_id);
+            let key = sra::tests::random_key(&mut self.rng);
+            let public_key = PublicKey::Sra(sra::pow_mod(&GENERATOR, &key));
+            let deposit = Balance::from(tokens) * self.game.config.chip_value;
+            self.game.enter(
+                account_id.to_string(),
+                public_key,
+                None,
+                deposit,
+                password.map(str::to_string),
+            )?;
+            self.keys.insert(account_id.to_string(), key);
+            Ok(())
+        }
+
+        pub fn account(&self, player_id: PlayerId) -> AccountId {
+            self.game.deck.get_players()[player_id as usize].clone()
+        }
+
+        /// Reveal the secret key of `account_id` for the last hand, registering a new one.
+        pub fn reveal_secret_key(&mut self, account_id: &str) -> Result<(), GameError> {
+            self.act_as(account_id);
+            let secret_key = self.keys[account_id];
+            let new_key = sra::tests::random_key(&mut self.rng);
+            self.game.submit_secret_key(
+                SecretKey::Sra(secret_key),
+                Some(PublicKey::Sra(sra::pow_mod(&GENERATOR, &new_key))),
+            )?;
+            self.keys.insert(account_id.to_string(), new_key);
+            Ok(())
+        }
+
+        pub fn reveal_secret_keys(&mut self) {
+            for account_id in self.game.deck.get_players() {
+                if self.game.deck.is_secret_key_pending(&account_id) {
+                    self.reveal_secret_key(&account_id).unwrap();
+                }
+            }
+        }
+
+        /// Reveal pending secret keys, get every player ready and start the hand.
+        pub fn start_hand(&mut self) {
+            self.reveal_secret_keys();
+            for account_id in self.game.deck.get_players() {
+                self.act_as(&account_id);
+                self.game.ready(account_id).unwrap();
+            }
+            if self.game.status != GameStatus::DeckAction {
+                let account_id = self.account(0);
+                self.act_as(&account_id);
+                self.game.start(account_id).unwrap();
+            }
+        }
+
+        /// Make every action on the deck until a player should bet or the hand is over.
+        pub fn play_deck(&mut self) {
+            while self.game.status == GameStatus::DeckAction {
+                let deck = match &self.game.deck {
+                    AnyDeck::Sra(deck) => deck.clone(),
+                    AnyDeck::ElGamal(_) => panic!("Tables use SRA cards."),
+                };
+                let turn = deck.get_turn().unwrap();
+                let account_id = self.account(turn);
+                let key = self.keys[&account_id];
+                self.act_as(&account_id);
+
+                match deck.get_status() {
+                    DeckStatus::Shuffling(_) => {
+                        let cards = deck.get_partial_shuffle().unwrap();
+                        let (new_cards, proof) = sra::tests::shuffle(&mut self.rng, &key, &cards);
+                        self.game
+                            .submit_shuffled(Cards::Sra(new_cards), ShuffleProof::Sra(proof))
+                            .unwrap();
+                    }
+                    DeckStatus::Revealing { receiver, .. } if receiver == Some(turn) => {
+                        self.game.finish_reveal().unwrap();
+                    }
+                    DeckStatus::Revealing { progress, .. } => {
+                        let (part, proof) = sra::tests::reveal(&mut self.rng, &key, &progress);
+                        self.game
+                            .submit_reveal_part(RevealPart::Sra(part), RevealProof::Sra(proof))
+                            .unwrap();
+                    }
+                    status => panic!("Unexpected deck status {:?}", status),
+                }
+            }
+        }
+
+        /// Play the rest of the hand. Every player calls or checks, and shows its cards.
+        pub fn play_until_idle(&mut self) {
+            loop {
+                self.play_deck();
+                if self.game.status != GameStatus::PokerAction {
+                    return;
+                }
+
+                let account_id = self.account(self.game.get_turn().unwrap());
+                self.act_as(&account_id);
+                if self.game.legal_actions().is_some() {
+                    self.game
+                        .submit_bet_action(BetAction::Call)
+                        .or_else(|_| self.game.submit_bet_action(BetAction::Check))
+                        .unwrap();
+                } else {
+                    self.game
+                        .submit_showdown_action(ShowdownAction::Show)
+                        .unwrap();
+                }
+            }
+        }
+
+        pub fn play_hand(&mut self) {
+            self.start_hand();
+            self.play_until_idle();
+        }
+    }
+
+    /// Amount paid by the contract since the mocked blockchain was set up.
+    fn paid() -> Balance {
+        get_context("poker").account_balance - env::account_balance()
+    }
+
+    #[test]
+    fn leave_pays_back_tokens_between_hands() {
+        let mut table = Table::new(config(), &["alice", "bob", "carol"], 100);
+        table.start_hand();
+        for account_id in &["alice", "bob"] {
+            table.act_as(account_id);
+            assert!(matches!(
+                table.game.leave(account_id.to_string()),
+                Err(GameError::OngoingRound)
+            ));
+        }
+        table.act_as("alice");
+        assert!(matches!(
+            table.game.kick("alice".to_string(), "bob".to_string()),
+            Err(GameError::OngoingRound)
+        ));
+
+        table.play_until_idle();
+        table.act_as("bob");
+        assert!(matches!(
+            table.game.leave("bob".to_string()),
+            Err(GameError::SecretKeyNotRevealed)
+        ));
+        table.act_as("alice");
+        assert!(matches!(
+            table.game.kick("alice".to_string(), "bob".to_string()),
+            Err(GameError::SecretKeyNotRevealed)
+        ));
+
+        let poker = table.game.poker_state();
+        let chip_value = table.game.config.chip_value;
+        table.reveal_secret_key("bob").unwrap();
+        table.act_as("alice");
+        table
+            .game
+            .kick("alice".to_string(), "bob".to_string())
+            .unwrap();
+        assert_eq!(paid(), Balance::from(poker.tokens(1)) * chip_value);
+
+        // Carol moved to the seat of bob.
+        assert_eq!(table.account(1), "carol".to_string());
+        assert_eq!(table.game.poker_state().tokens(1), poker.tokens(2));
+
+        table.reveal_secret_key("alice").unwrap();
+        table.act_as("alice");
+        table.game.leave("alice".to_string()).unwrap();
+
+        // Carol is the last player, and is paid as the winner.
+        let tokens = poker.tokens(0) + poker.tokens(2);
+        assert_eq!(paid(), Balance::from(tokens) * chip_value);
+        assert_eq!(table.game.winner, Some("carol".to_string()));
+        assert!(table.game.status == GameStatus::Closed);
+    }
+
+    /// Move to the first block where the timeout of the player in turn can be claimed, if
+    /// it has no time bank, and claim it as a spectator.
+    fn claim_timeout(table: &mut Table) -> Result<(), GameError> {
+        table.block = table.game.deadline().unwrap().at + 1;
+        table.act_as("zoe");
+        table.game.claim_timeout()
+    }
+
+    /// Call or check until the showdown.
+    fn play_until_showdown(table: &mut Table) {
+        loop {
+            table.play_deck();
+            let account_id = table.account(table.game.get_turn().unwrap());
+            table.act_as(&account_id);
+            if table.game.legal_actions().is_none() {
+                return;
+            }
+            table
+                .game
+                .submit_bet_action(BetAction::Call)
+                .or_else(|_| table.game.submit_bet_action(BetAction::Check))
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn claim_timeout_after_deadline() {
+        let mut table = Table::new(config(), &["alice", "bob", "carol"], 100);
+        table.act_as("zoe");
+        assert!(matches!(
+            table.game.claim_timeout(),
+            Err(GameError::DeadlineNotReached)
+        ));
+
+        table.start_hand();
+        table.block = table.game.deadline().unwrap().at;
+        table.act_as("zoe");
+        assert!(matches!(
+            table.game.claim_timeout(),
+            Err(GameError::DeadlineNotReached)
+        ));
+        assert!(claim_timeout(&mut table).is_ok());
+    }
+
+    #[test]
+    fn claim_timeout_folds_while_betting() {
+        let mut table = Table::new(config(), &["alice", "bob", "carol"], 100);
+        table.start_hand();
+        table.play_deck();
+
+        // Button 0, small blind 1, big blind 2. Both players before the big blind fold.
+        assert_eq!(table.game.deadline().unwrap().player_id, 0);
+        claim_timeout(&mut table).unwrap();
+        assert!(table.game.status == GameStatus::PokerAction);
+        assert_eq!(table.game.deadline().unwrap().player_id, 1);
+        claim_timeout(&mut table).unwrap();
+
+        let poker = table.game.poker_state();
+        assert!(table.game.status == GameStatus::Idle);
+        assert_eq!(
+            (poker.tokens(0), poker.tokens(1), poker.tokens(2)),
+            (100, 97, 103)
+        );
+        assert!(table.game.slashed.is_empty());
+    }
+
+    #[test]
+    fn claim_timeout_mucks_at_showdown() {
+        let mut table = Table::new(config(), &["alice", "bob", "carol"], 100);
+        table.start_hand();
+        play_until_showdown(&mut table);
+
+        let player_id = table.game.get_turn().unwrap();
+        claim_timeout(&mut table).unwrap();
+        assert_ne!(table.game.get_turn(), Some(player_id));
+        table.play_until_idle();
+        assert!(table.game.status == GameStatus::Idle);
+        assert!(table.game.slashed.is_empty());
+    }
+
+    #[test]
+    fn claim_timeout_skips_straddle() {
+        let config = TableConfig {
+            straddle: true,
+            ..config()
+        };
+        let mut table = Table::new(config, &["alice", "bob", "carol"], 100);
+        table.start_hand();
+        table.play_deck();
+
+        // Alice, after the big blind, doesn't straddle.
+        assert!(
+            table.game.poker_state().get_status() == PokerStatus::StraddleDecision { player_id: 0 }
+        );
+        claim_timeout(&mut table).unwrap();
+        table.play_deck();
+        assert!(table.game.legal_actions().is_some());
+        assert_eq!(table.game.get_turn(), Some(0));
+        assert_eq!(table.game.poker_state().tokens(0), 100);
+    }
+
+    #[test]
+    fn claim_timeout_slashes_on_deck_action() {
+        let mut table = Table::new(config(), &["alice", "bob", "carol"], 100);
+        table.start_hand();
+
+        // Alice doesn't shuffle. The hand is cancelled and her tokens are split.
+        assert_eq!(table.game.deadline().unwrap().player_id, 0);
+        claim_timeout(&mut table).unwrap();
+        assert_eq!(table.game.slashed, vec!["alice".to_string()]);
+        assert_eq!(
+            table.game.deck.get_players(),
+            vec!["bob".to_string(), "carol".to_string()]
+        );
+        let poker = table.game.poker_state();
+        assert_eq!((poker.tokens(0), poker.tokens(1)), (150, 150));
+        assert!(table.game.status == GameStatus::Idle);
+
+        table.play_hand();
+        assert!(table.game.status == GameStatus::Idle);
+    }
+
+    #[test]
+    fn claim_timeout_slashes_without_secret_key() {
+        let mut table = Table::new(config(), &["alice", "bob", "carol"], 100);
+        table.play_hand();
+        table.reveal_secret_key("alice").unwrap();
+        table.reveal_secret_key("carol").unwrap();
+
+        assert_eq!(table.game.deadline().unwrap().player_id, 1);
+        let poker = table.game.poker_state();
+        claim_timeout(&mut table).unwrap();
+        assert_eq!(table.game.slashed, vec!["bob".to_string()]);
+        let after = table.game.poker_state();
+        assert_eq!(after.tokens(0) + after.tokens(1), 300);
+        assert!(after.tokens(0) > poker.tokens(0));
+        assert!(table.game.deadline().is_none());
+        table.play_hand();
+    }
+
+    /// Table where players have a time bank of 20 blocks, topped up by 5 after each hand.
+    fn time_bank_table() -> Table {
+        let config = TableConfig {
+            time_bank: Some(TimeBank {
+                initial: 20,
+                top_up: 5,
+                every_hands: 1,
+            }),
+            ..config()
+        };
+        Table::new(config, &["alice", "bob", "carol"], 100)
+    }
+
+    fn time_banks(table: &Table) -> Vec<u64> {
+        let poker = table.game.poker_state();
+        (0..3).map(|player_id| poker.time_bank(player_id)).collect()
+    }
+
+    #[test]
+    fn time_bank_is_used_after_deadline() {
+        let mut table = time_bank_table();
+        table.start_hand();
+        let deadline = table.game.deadline().unwrap();
+        assert_eq!(deadline.player_id, 0);
+
+        // Alice shuffles 7 blocks late.
+        table.block = deadline.at + 7;
+        table.play_deck();
+        assert_eq!(time_banks(&table), vec![13, 20, 20]);
+
+        // Alice is the first to bet, and can use the rest of her time bank before the
+        // timeout is claimed.
+        let deadline = table.game.deadline().unwrap();
+        assert_eq!(deadline.player_id, 0);
+        table.block = deadline.at + 13;
+        table.act_as("zoe");
+        assert!(matches!(
+            table.game.claim_timeout(),
+            Err(GameError::DeadlineNotReached)
+        ));
+        table.block += 1;
+        table.act_as("zoe");
+        table.game.claim_timeout().unwrap();
+        assert_eq!(time_banks(&table), vec![0, 20, 20]);
+    }
+
+    #[test]
+    fn time_bank_top_up_is_capped() {
+        let mut table = time_bank_table();
+        table.start_hand();
+        table.block = table.game.deadline().unwrap().at + 7;
+        table.play_until_idle();
+        assert_eq!(time_banks(&table), vec![18, 20, 20]);
+
+        table.play_hand();
+        assert_eq!(time_banks(&table), vec![20, 20, 20]);
+    }
+
+    #[test]
+    fn start_when_everyone_is_ready() {
+        let mut table = Table::new(config(), &["alice", "bob"], 100);
+        table.act_as("alice");
+        table.game.ready("alice".to_string()).unwrap();
+        assert!(matches!(
+            table.game.start("alice".to_string()),
+            Err(GameError::PlayersNotReady)
+        ));
+
+        table.act_as("bob");
+        table.game.ready("bob".to_string()).unwrap();
+        assert!(table.game.status == GameStatus::Initiating);
+        table.act_as("zoe");
+        assert!(matches!(
+            table.game.ready("zoe".to_string()),
+            Err(GameError::NotAPlayer)
+        ));
+        assert!(matches!(
+            table.game.start("zoe".to_string()),
+            Err(GameError::NotAPlayer)
+        ));
+
+        // Any player can start the hand.
+        table.act_as("bob");
+        table.game.start("bob".to_string()).unwrap();
+        assert!(table.game.status == GameStatus::DeckAction);
+        assert!(matches!(
+            table.game.ready("bob".to_string()),
+            Err(GameError::OngoingRound)
+        ));
+
+        table.play_until_idle();
+        assert!(matches!(
+            table.game.ready("bob".to_string()),
+            Err(GameError::SecretKeyNotRevealed)
+        ));
+    }
+
+    #[test]
+    fn auto_start_when_everyone_is_ready() {
+        let config = TableConfig {
+            auto_start: true,
+            min_players: 3,
+            ..config()
+        };
+        let mut table = Table::new(config, &["alice", "bob"], 100);
+        for account_id in &["alice", "bob"] {
+            table.act_as(account_id);
+            table.game.ready(account_id.to_string()).unwrap();
+        }
+        assert!(table.game.status == GameStatus::Initiating);
+
+        // Carol enters without being ready.
+        table.enter("carol", 100).unwrap();
+        table.act_as("alice");
+        table.game.pause("alice".to_string()).unwrap();
+        table.act_as("carol");
+        table.game.ready("carol".to_string()).unwrap();
+        assert!(table.game.status == GameStatus::Initiating);
+
+        table.act_as("alice");
+        table.game.resume("alice".to_string()).unwrap();
+        assert!(table.game.status == GameStatus::DeckAction);
+
+        // The next hand starts once every key is revealed and every player is ready.
+        table.play_until_idle();
+        table.reveal_secret_keys();
+        for account_id in &["alice", "bob", "carol"] {
+            assert!(table.game.status == GameStatus::Idle);
+            table.act_as(account_id);
+            table.game.ready(account_id.to_string()).unwrap();
+        }
+        assert!(table.game.status == GameStatus::DeckAction);
+    }
+
+    #[test]
+    fn only_the_host_manages_the_room() {
+        let mut table = Table::new(config(), &["alice", "bob", "carol"], 100);
+        table.act_as("bob");
+        let bob = "bob".to_string();
+        assert!(matches!(
+            table.game.kick(bob.clone(), "carol".to_string()),
+            Err(GameError::NotHost)
+        ));
+        assert!(matches!(
+            table.game.pause(bob.clone()),
+            Err(GameError::NotHost)
+        ));
+        assert!(matches!(
+            table.game.resume(bob.clone()),
+            Err(GameError::NotHost)
+        ));
+        assert!(matches!(
+            table.game.set_access(bob.clone(), Access::Invite(vec![])),
+            Err(GameError::NotHost)
+        ));
+        assert!(matches!(
+            table.game.transfer_host(bob.clone(), bob.clone()),
+            Err(GameError::NotHost)
+        ));
+        assert!(matches!(
+            table.game.close(bob.clone()),
+            Err(GameError::NotHost)
+        ));
+
+        // The host can only be transferred to a player.
+        table.act_as("alice");
+        assert!(matches!(
+            table
+                .game
+                .transfer_host("alice".to_string(), "zoe".to_string()),
+            Err(GameError::NotAPlayer)
+        ));
+        table
+            .game
+            .transfer_host("alice".to_string(), bob.clone())
+            .unwrap();
+        assert!(matches!(
+            table.game.pause("alice".to_string()),
+            Err(GameError::NotHost)
+        ));
+
+        table.act_as("bob");
+        table.game.kick(bob.clone(), "alice".to_string()).unwrap();
+        assert_eq!(
+            table.game.deck.get_players(),
+            vec![bob.clone(), "carol".to_string()]
+        );
+        table.game.close(bob).unwrap();
+        assert!(table.game.status == GameStatus::Closed);
+        // Every player was paid back, alice when she was kicked.
+        assert_eq!(paid(), 300 * table.game.config.chip_value);
+    }
+
+    #[test]
+    fn close_between_hands() {
+        let mut table = Table::new(config(), &["alice", "bob"], 100);
+        table.start_hand();
+        table.act_as("alice");
+        assert!(matches!(
+            table.game.close("alice".to_string()),
+            Err(GameError::OngoingRound)
+        ));
+
+        table.play_until_idle();
+        table.reveal_secret_key("alice").unwrap();
+        table.act_as("alice");
+        assert!(matches!(
+            table.game.close("alice".to_string()),
+            Err(GameError::SecretKeyNotRevealed)
+        ));
+
+        table.reveal_secret_key("bob").unwrap();
+        table.act_as("alice");
+        table.game.close("alice".to_string()).unwrap();
+        assert!(table.game.status == GameStatus::Closed);
+        assert_eq!(paid(), 200 * table.game.config.chip_value);
+    }
+
+    #[test]
+    fn enter_with_password() {
+        let mut table = Table::new(config(), &["alice"], 100);
+        // The hash is not case sensitive.
+        let access = Access::Password {
+            salt: "salt".to_string(),
+            hash: to_hex(&env::sha256(b"saltsecret")).to_uppercase(),
+        };
+        table.game.set_access("alice".to_string(), access).unwrap();
+        assert!(table.game.is_private());
+
+        for password in &[None, Some("wrong"), Some("saltsecret")] {
+            assert!(matches!(
+                table.enter_with_password("bob", 100, *password),
+                Err(GameError::InvalidPassword)
+            ));
+        }
+        table
+            .enter_with_password("bob", 100, Some("secret"))
+            .unwrap();
+
+        // Only the host and players see the room.
+        let visible = |account_id: &str| table.game.is_visible_to(Some(&account_id.to_string()));
+        assert!(visible("alice") && visible("bob") && !visible("carol"));
+        assert!(!table.game.is_visible_to(None));
+    }
+
+    #[test]
+    fn enter_with_invite() {
+        let mut table = Table::new(config(), &["alice", "bob"], 100);
+        let access = Access::Invite(vec!["carol".to_string()]);
+        table.game.set_access("alice".to_string(), access).unwrap();
+
+        assert!(matches!(
+            table.enter("dave", 100),
+            Err(GameError::NotInvited)
+        ));
+        table.enter("carol", 100).unwrap();
+
+        // Bob was seated before the room was private, and the host can always enter.
+        table.act_as("alice");
+        table.game.leave("alice".to_string()).unwrap();
+        table.enter("alice", 100).unwrap();
+        assert_eq!(table.game.deck.num_players(), 3);
+
+        let visible = |account_id: &str| table.game.is_visible_to(Some(&account_id.to_string()));
+        assert!(visible("alice") && visible("bob") && visible("carol") && !visible("dave"));
+    }
+
+    #[test]
+    fn deadline_follows_seats_after_leaving() {
+        let mut table = Table::new(config(), &["alice", "bob", "carol", "dave"], 100);
+        table.play_hand();
+        assert!(table.game.status == GameStatus::Idle);
+        table.reveal_secret_key("alice").unwrap();
+        table.reveal_secret_key("bob").unwrap();
+        let deadline = table.game.deadline().unwrap();
+        assert_eq!(deadline.player_id, 2);
+
+        // Carol waits to reveal as player 1 once bob is kicked, and as player 0 once
+        // alice leaves.
+        table.act_as("alice");
+        table
+            .game
+            .kick("alice".to_string(), "bob".to_string())
+            .unwrap();
+        assert_eq!(table.game.deadline().unwrap().player_id, 1);
+        assert_eq!(table.game.deadline().unwrap().at, deadline.at);
+        table.game.leave("alice".to_string()).unwrap();
+        assert_eq!(table.game.deadline().unwrap().player_id, 0);
+
+        table.block = deadline.at + 1;
+        table.act_as("dave");
+        table.game.claim_timeout().unwrap();
+        assert_eq!(table.game.slashed, vec!["carol".to_string()]);
+        assert_eq!(table.game.winner, Some("dave".to_string()));
+        assert!(table.game.deadline().is_none());
+    }
 }
@@ -0,0 +1,227 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::Serialize;
+
+/// Total number of cards in a french deck.
+pub const DECK_SIZE: u64 = 52;
+
+/// Number of cards on the table once all the stages were revealed.
+pub const BOARD_SIZE: u64 = 5;
+
+/// Number of cards that make a poker hand.
+const HAND_SIZE: usize = 5;
+
+/// Rank of the ace when it is played as the highest card.
+const ACE: u8 = 12;
+
+/// Rank of the five. Highest card of the lowest straight (A-2-3-4-5).
+const FIVE: u8 = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Card {
+    /// From 0 (two) to 12 (ace).
+    pub rank: u8,
+    /// From 0 to 3.
+    pub suit: u8,
+}
+
+impl Card {
+    pub fn new(card: u64) -> Option<Self> {
+        if card < DECK_SIZE {
+            Some(Self {
+                rank: (card % 13) as u8,
+                suit: (card / 13) as u8,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug,
+)]
+pub enum HandCategory {
+    HighCard,
+    OnePair,
+    TwoPair,
+    ThreeOfAKind,
+    Straight,
+    Flush,
+    FullHouse,
+    FourOfAKind,
+    StraightFlush,
+}
+
+/// Value of the best five cards of a player. Hands are compared first by category
+/// and then by the ranks that break ties inside the category, so two hands with
+/// the same value are an exact tie.
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Clone, PartialEq, Eq, PartialOrd, Ord, Debug,
+)]
+pub struct HandValue {
+    pub category: HandCategory,
+    /// Ranks in the order they are compared. Grouped cards come first (most
+    /// repeated, then highest), followed by the kickers from highest to lowest.
+    /// Straights only keep its highest card.
+    pub ranks: Vec<u8>,
+}
+
+/// Find the value of the best five cards hand that can be made using `cards`.
+/// It panics if there are less than five cards.
+pub fn evaluate(cards: &[Card]) -> HandValue {
+    assert!(cards.len() >= HAND_SIZE, "Not enough cards to make a hand.");
+
+    let mut best: Option<HandValue> = None;
+    let mut hand = Vec::with_capacity(HAND_SIZE);
+    combinations(cards, 0, &mut hand, &mut best);
+    best.unwrap()
+}
+
+/// Evaluate every subset of HAND_SIZE cards and keep the best one in `best`.
+fn combinations(cards: &[Card], start: usize, hand: &mut Vec<Card>, best: &mut Option<HandValue>) {
+    if hand.len() == HAND_SIZE {
+        let value = evaluate_five(hand);
+        if best.as_ref().map_or(true, |best| value > *best) {
+            *best = Some(value);
+        }
+        return;
+    }
+
+    for i in start..cards.len() {
+        if cards.len() - i < HAND_SIZE - hand.len() {
+            break;
+        }
+        hand.push(cards[i]);
+        combinations(cards, i + 1, hand, best);
+        hand.pop();
+    }
+}
+
+fn evaluate_five(hand: &[Card]) -> HandValue {
+    let is_flush = hand.iter().all(|card| card.suit == hand[0].suit);
+
+    let mut count = [0u8; 13];
+    for card in hand {
+        count[card.rank as usize] += 1;
+    }
+
+    // Groups of cards with the same rank, sorted by size and then by rank.
+    let mut groups: Vec<(u8, u8)> = (0..13u8)
+        .rev()
+        .filter(|&rank| count[rank as usize] > 0)
+        .map(|rank| (count[rank as usize], rank))
+        .collect();
+    groups.sort_by(|a, b| b.cmp(a));
+
+    let ranks: Vec<u8> = groups
+        .iter()
+        .flat_map(|&(size, rank)| std::iter::repeat(rank).take(size as usize))
+        .collect();
+
+    let straight_high = if groups.len() == HAND_SIZE {
+        if ranks[0] - ranks[4] == 4 {
+            Some(ranks[0])
+        } else if ranks[0] == ACE && ranks[1] == FIVE {
+            // Ace plays as the lowest card.
+            Some(FIVE)
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+
+    let category = match (straight_high, is_flush, groups[0].0, groups[1].0) {
+        (Some(_), true, _, _) => HandCategory::StraightFlush,
+        (_, _, 4, _) => HandCategory::FourOfAKind,
+        (_, _, 3, 2) => HandCategory::FullHouse,
+        (_, true, _, _) => HandCategory::Flush,
+        (Some(_), _, _, _) => HandCategory::Straight,
+        (_, _, 3, _) => HandCategory::ThreeOfAKind,
+        (_, _, 2, 2) => HandCategory::TwoPair,
+        (_, _, 2, _) => HandCategory::OnePair,
+        _ => HandCategory::HighCard,
+    };
+
+    let ranks = match category {
+        HandCategory::StraightFlush | HandCategory::Straight => vec![straight_high.unwrap()],
+        _ => ranks,
+    };
+
+    HandValue { category, ranks }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse cards such as `"As Td 2c"`.
+    fn cards(hand: &str) -> Vec<Card> {
+        hand.split_whitespace()
+            .map(|card| {
+                let mut chars = card.chars();
+                let rank = "23456789TJQKA".find(chars.next().unwrap()).unwrap();
+                let suit = "cdhs".find(chars.next().unwrap()).unwrap();
+                Card {
+                    rank: rank as u8,
+                    suit: suit as u8,
+                }
+            })
+            .collect()
+    }
+
+    fn value(hand: &str) -> HandValue {
+        evaluate(&cards(hand))
+    }
+
+    #[test]
+    fn wheel() {
+        let wheel = value("As 2d 3c 4h 5s Kd 9c");
+        assert_eq!(wheel.category, HandCategory::Straight);
+        assert_eq!(wheel.ranks, vec![FIVE]);
+        assert!(wheel < value("2d 3c 4h 5s 6s Kd 9c"));
+        assert!(wheel > value("As Ad Kc Kh 5s 8d 9c"));
+
+        let steel_wheel = value("Ah 2h 3h 4h 5h Kd 9c");
+        assert_eq!(steel_wheel.category, HandCategory::StraightFlush);
+        assert_eq!(steel_wheel.ranks, vec![FIVE]);
+    }
+
+    #[test]
+    fn kickers() {
+        assert!(value("As Ad Kc 7h 5s 3d 2c") > value("As Ad Qc Jh 9s 3d 2c"));
+        assert!(value("Ks Kd 8c 8h 9s 3d 2c") > value("Ks Kd 8c 8h 7s 6d 2c"));
+        assert!(value("Ah Jh 9h 6h 3h") > value("Ah Jh 9h 6h 2h"));
+        // Only the best five cards count.
+        assert_eq!(value("As Ad Kc Qh Js 3d 2c"), value("As Ad Kc Qh Js 4d 3c"));
+    }
+
+    #[test]
+    fn flush_beats_straight() {
+        let flush = value("2h 7h 9h Jh Kh 8c Tc");
+        assert_eq!(flush.category, HandCategory::Flush);
+        let straight = value("7s 8d 9c Th Js 2c 3c");
+        assert_eq!(straight.category, HandCategory::Straight);
+        assert!(flush > straight);
+    }
+
+    #[test]
+    fn full_house_from_two_trips() {
+        let full_house = value("Ks Kd Kc 4h 4s 4d 9c");
+        assert_eq!(full_house.category, HandCategory::FullHouse);
+        assert_eq!(full_house.ranks, vec![11, 11, 11, 2, 2]);
+        assert!(full_house > value("Qs Qd Qc As Ad 9c 2h"));
+    }
+
+    #[test]
+    fn exact_ties() {
+        // Both players play the board.
+        let board = "Ts Jd Qc Kh As";
+        let first = value(&format!("{} 2c 3d", board));
+        let second = value(&format!("{} 4c 5d", board));
+        assert_eq!(first, second);
+
+        // Same ranks in different suits.
+        assert_eq!(value("As Ad 9c 7h 5s"), value("Ac Ah 9d 7s 5d"));
+    }
+}
@@ -1,5 +1,14 @@
+mod bigint;
+mod config;
 mod deck;
+mod elgamal;
 mod game;
+mod hand;
 pub mod lobby;
 mod poker;
+mod pot;
+mod scheme;
+mod sra;
+#[cfg(test)]
+mod test_utils;
 mod types;
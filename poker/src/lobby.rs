@@ -1,12 +1,17 @@
-use crate::deck::Deck;
-use crate::game::{Game, GameError, GameStatus};
+use crate::config::TableConfig;
+use crate::deck::AnyDeck;
+use crate::game::{Access, Deadline, Game, GameError, GameState, GameStatus, RoundResult};
 use crate::poker::BetAction;
+use crate::poker::BlindLevelInfo;
+use crate::poker::LegalActions;
 use crate::poker::Poker;
-use crate::types::PlayerId;
-use crate::types::{CryptoHash, RoomId};
+use crate::poker::ShowdownAction;
+use crate::scheme::{Cards, PublicKey, RevealPart, RevealProof, SecretKey, ShuffleProof};
+use crate::types::RoomId;
+use crate::types::{AccountId, Balance, PlayerId};
 use borsh::{BorshDeserialize, BorshSerialize};
-use near_bindgen::near_bindgen;
-use serde::Serialize;
+use near_bindgen::{env, near_bindgen, Promise, PromiseResult};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 #[global_allocator]
@@ -17,6 +22,15 @@ pub struct RoomInfo {
     name: String,
     id: RoomId,
     status: GameStatus,
+    config: TableConfig,
+    winner: Option<AccountId>,
+    host: AccountId,
+    paused: bool,
+    /// Entering the room requires a password or an invitation.
+    private: bool,
+    /// Players caught cheating when auditing a hand, or that left it without revealing
+    /// their secret key.
+    cheaters: Vec<AccountId>,
 }
 
 impl From<&Game> for RoomInfo {
@@ -25,10 +39,25 @@ impl From<&Game> for RoomInfo {
             name: poker.name.clone(),
             id: poker.id,
             status: poker.status.clone(),
+            config: poker.config.clone(),
+            winner: poker.winner.clone(),
+            host: poker.host.clone(),
+            paused: poker.paused,
+            private: poker.is_private(),
+            cheaters: poker.cheaters.clone(),
         }
     }
 }
 
+/// `msg` of `ft_transfer_call` to enter a room, with the same arguments as `enter`.
+#[derive(Deserialize)]
+struct EnterMessage {
+    room_id: RoomId,
+    public_key: PublicKey,
+    #[serde(default)]
+    password: Option<String>,
+}
+
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, Default, Serialize)]
 pub struct Lobby {
@@ -45,21 +74,38 @@ impl Lobby {
         }
     }
 
-    pub fn new_room(&mut self, name: String) -> RoomId {
+    /// Create a new room. Default table parameters are used if `config` is not specified.
+    /// The room is public if `access` is not specified.
+    pub fn new_room(
+        &mut self,
+        name: String,
+        config: Option<TableConfig>,
+        access: Option<Access>,
+    ) -> Result<RoomId, GameError> {
+        let poker = Game::new(
+            name,
+            self.last_room,
+            config.unwrap_or_default(),
+            env::predecessor_account_id(),
+            access.unwrap_or_default(),
+        )?;
         let room_id = self.last_room;
         self.last_room += 1;
-        let poker = Game::new(name, room_id);
         self.rooms.insert(room_id, poker);
-        room_id
+        Ok(room_id)
     }
 
-    pub fn all_rooms(&self) -> Vec<RoomInfo> {
-        self.rooms.values().map(Into::into).collect()
+    /// Rooms listed to `account_id`. Private rooms are only listed to their host, players
+    /// and invited accounts. This is a convenience filter, not a privacy guarantee: it is a
+    /// view method, so anyone can pass any account, and the state of the contract is public.
+    pub fn all_rooms(&self, account_id: Option<AccountId>) -> Vec<RoomInfo> {
+        self.visible_rooms(account_id.as_ref())
+            .map(Into::into)
+            .collect()
     }
 
-    pub fn all_active_rooms(&self) -> Vec<RoomInfo> {
-        self.rooms
-            .values()
+    pub fn all_active_rooms(&self, account_id: Option<AccountId>) -> Vec<RoomInfo> {
+        self.visible_rooms(account_id.as_ref())
             .filter_map(|val| {
                 if val.status.is_active() {
                     Some(val)
@@ -71,9 +117,8 @@ impl Lobby {
             .collect()
     }
 
-    pub fn all_initiating_rooms(&self) -> Vec<RoomInfo> {
-        self.rooms
-            .values()
+    pub fn all_initiating_rooms(&self, account_id: Option<AccountId>) -> Vec<RoomInfo> {
+        self.visible_rooms(account_id.as_ref())
             .filter_map(|val| {
                 if val.status.is_initiating() {
                     Some(val)
@@ -85,6 +130,15 @@ impl Lobby {
             .collect()
     }
 
+    fn visible_rooms<'a>(
+        &'a self,
+        account_id: Option<&'a AccountId>,
+    ) -> impl Iterator<Item = &'a Game> + 'a {
+        self.rooms
+            .values()
+            .filter(move |room| room.is_visible_to(account_id))
+    }
+
     fn room_ref(&self, room_id: RoomId) -> Result<&Game, GameError> {
         self.rooms.get(&room_id).ok_or(GameError::RoomIdNotFound)
     }
@@ -94,24 +148,119 @@ impl Lobby {
             .get_mut(&room_id)
             .ok_or(GameError::RoomIdNotFound)
     }
+
+    /// Keep the payout of `amount` to `account_id` on the room if the transfer failed.
+    fn check_payout(&mut self, room_id: RoomId, account_id: AccountId, amount: String) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            env::current_account_id(),
+            "Only the contract can call this method."
+        );
+
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            return;
+        }
+
+        let amount: Balance = amount.parse().expect("Invalid amount.");
+        self.room_mut(room_id)
+            .expect("Room of the payout not found.")
+            .payout_failed(account_id, amount);
+    }
 }
 
 /// Game interface for Lobby
 #[near_bindgen]
 impl Lobby {
-    pub fn enter(&mut self, room_id: RoomId) -> Result<(), GameError> {
-        self.room_mut(room_id)?.enter()
+    /// Enter a room. Attach the buy-in as deposit. `public_key` is used to check the cards
+    /// submitted by the player, and should belong to the card scheme of the room.
+    /// `password` is required to enter rooms protected with a password. If it is not
+    /// possible to enter the room the deposit is refunded.
+    pub fn enter(
+        &mut self,
+        room_id: RoomId,
+        public_key: PublicKey,
+        password: Option<String>,
+    ) -> Result<(), GameError> {
+        // The seat and the refund go to the account that attached the deposit.
+        let account_id = env::predecessor_account_id();
+        let deposit = env::attached_deposit();
+        let result = self
+            .room_mut(room_id)
+            .and_then(|room| room.enter(account_id.clone(), public_key, None, deposit, password));
+
+        if result.is_err() && deposit > 0 {
+            Promise::new(account_id).transfer(deposit);
+        }
+        result
+    }
+
+    /// Mark the caller as ready to start the next hand.
+    pub fn ready(&mut self, room_id: RoomId) -> Result<(), GameError> {
+        self.room_mut(room_id)?.ready(env::predecessor_account_id())
     }
 
+    /// Start the next hand once every player is ready. Only players can call it.
     pub fn start(&mut self, room_id: RoomId) -> Result<(), GameError> {
-        self.room_mut(room_id)?.start()
+        self.room_mut(room_id)?.start(env::predecessor_account_id())
     }
 
+    /// Block index or timestamp before which the player in turn should act, or players
+    /// should reveal the secret key used in the last hand.
+    pub fn deadline(&self, room_id: RoomId) -> Result<Option<Deadline>, GameError> {
+        Ok(self.room_ref(room_id)?.deadline())
+    }
+
+    /// Unblock a room where the player in turn didn't act, or a player didn't reveal its
+    /// secret key, before the deadline. Anyone can call it.
+    pub fn claim_timeout(&mut self, room_id: RoomId) -> Result<(), GameError> {
+        self.room_mut(room_id)?.claim_timeout()
+    }
+
+    /// Leave a room between rounds. The value of the remaining tokens is paid back.
+    pub fn leave(&mut self, room_id: RoomId) -> Result<(), GameError> {
+        self.room_mut(room_id)?.leave(env::predecessor_account_id())
+    }
+
+    /// Close the room paying back the tokens to all players. Only the host can close it.
     pub fn close(&mut self, room_id: RoomId) -> Result<(), GameError> {
-        self.room_mut(room_id)?.close()
+        self.room_mut(room_id)?.close(env::predecessor_account_id())
+    }
+
+    /// Stop starting new hands in the room. Only the host can pause it.
+    pub fn pause(&mut self, room_id: RoomId) -> Result<(), GameError> {
+        self.room_mut(room_id)?.pause(env::predecessor_account_id())
     }
 
-    pub fn deck_state(&self, room_id: RoomId) -> Result<Deck, GameError> {
+    /// Allow starting new hands in a paused room. Only the host can resume it.
+    pub fn resume(&mut self, room_id: RoomId) -> Result<(), GameError> {
+        self.room_mut(room_id)?
+            .resume(env::predecessor_account_id())
+    }
+
+    /// Remove `account_id` from the room between hands paying back its tokens.
+    /// Only the host can kick players.
+    pub fn kick(&mut self, room_id: RoomId, account_id: AccountId) -> Result<(), GameError> {
+        self.room_mut(room_id)?
+            .kick(env::predecessor_account_id(), account_id)
+    }
+
+    /// Change who can enter the room. Only the host can call it.
+    pub fn set_access(&mut self, room_id: RoomId, access: Access) -> Result<(), GameError> {
+        self.room_mut(room_id)?
+            .set_access(env::predecessor_account_id(), access)
+    }
+
+    /// Make `account_id`, which should be a player, the new host of the room.
+    pub fn transfer_host(
+        &mut self,
+        room_id: RoomId,
+        account_id: AccountId,
+    ) -> Result<(), GameError> {
+        self.room_mut(room_id)?
+            .transfer_host(env::predecessor_account_id(), account_id)
+    }
+
+    pub fn deck_state(&self, room_id: RoomId) -> Result<AnyDeck, GameError> {
         Ok(self.room_ref(room_id)?.deck_state())
     }
 
@@ -119,19 +268,90 @@ impl Lobby {
         Ok(self.room_ref(room_id)?.poker_state())
     }
 
-    pub fn state(&self, room_id: RoomId) -> Result<GameStatus, GameError> {
+    pub fn state(&self, room_id: RoomId) -> Result<GameState, GameError> {
         Ok(self.room_ref(room_id)?.state())
     }
 
+    pub fn last_settlement(&self, room_id: RoomId) -> Result<Option<RoundResult>, GameError> {
+        Ok(self.room_ref(room_id)?.last_settlement())
+    }
+
     pub fn get_turn(&self, room_id: RoomId) -> Result<Option<PlayerId>, GameError> {
         Ok(self.room_ref(room_id)?.get_turn())
     }
+
+    pub fn blind_level(&self, room_id: RoomId) -> Result<BlindLevelInfo, GameError> {
+        Ok(self.room_ref(room_id)?.blind_level())
+    }
+
+    /// Actions available to the player that should bet. None if no player is betting.
+    pub fn legal_actions(&self, room_id: RoomId) -> Result<Option<LegalActions>, GameError> {
+        Ok(self.room_ref(room_id)?.legal_actions())
+    }
+
+    /// Value of the tokens held on behalf of `account_id` in a room, in the smallest unit
+    /// of the currency of the room, as a decimal string.
+    pub fn escrowed_balance(
+        &self,
+        room_id: RoomId,
+        account_id: AccountId,
+    ) -> Result<String, GameError> {
+        Ok(self
+            .room_ref(room_id)?
+            .escrowed_balance(&account_id)
+            .to_string())
+    }
+
+    /// Receiver of NEP-141 `ft_transfer_call`. Enter the room in `msg` as `sender_id`
+    /// buying tokens with `amount`. `msg` is `{"room_id": .., "public_key": .., "password": ..}`,
+    /// where the password is only required on rooms protected with a password. The room
+    /// should use the calling token contract.
+    /// Return the amount to refund, which is everything if it is not possible to enter.
+    pub fn ft_on_transfer(&mut self, sender_id: AccountId, amount: String, msg: String) -> String {
+        let amount: Balance = amount.parse().expect("Invalid amount.");
+        let token_account_id = env::predecessor_account_id();
+
+        let result = serde_json::from_str::<EnterMessage>(&msg)
+            .map_err(|_| GameError::RoomIdNotFound)
+            .and_then(|msg| {
+                self.room_mut(msg.room_id)?.enter(
+                    sender_id,
+                    msg.public_key,
+                    Some(&token_account_id),
+                    amount,
+                    msg.password,
+                )
+            });
+
+        match result {
+            Ok(()) => "0".to_string(),
+            Err(_) => amount.to_string(),
+        }
+    }
+
+    /// Callback after paying NEAR. If the transfer failed, for example because the account
+    /// doesn't exist, the amount is kept on the room so the player can withdraw it again.
+    pub fn on_near_payout(&mut self, room_id: RoomId, account_id: AccountId, amount: String) {
+        self.check_payout(room_id, account_id, amount);
+    }
+
+    /// Callback after paying fungible tokens. If the transfer failed, the amount is kept
+    /// on the room so the player can withdraw it again.
+    pub fn on_ft_payout(&mut self, room_id: RoomId, account_id: AccountId, amount: String) {
+        self.check_payout(room_id, account_id, amount);
+    }
+
+    /// Retry payouts to the caller that failed in a room.
+    pub fn withdraw(&mut self, room_id: RoomId) -> Result<(), GameError> {
+        self.room_mut(room_id)?
+            .withdraw(env::predecessor_account_id())
+    }
 }
 
 /// Deck interface for Lobby
 #[near_bindgen]
 impl Lobby {
-    pub fn get_partial_shuffle(&self, room_id: RoomId) -> Result<Vec<CryptoHash>, GameError> {
+    pub fn get_partial_shuffle(&self, room_id: RoomId) -> Result<Cards, GameError> {
         self.room_ref(room_id)?
             .get_partial_shuffle()
             .map_err(Into::into)
@@ -140,10 +360,11 @@ impl Lobby {
     pub fn submit_shuffled(
         &mut self,
         room_id: RoomId,
-        new_cards: Vec<CryptoHash>,
+        new_cards: Cards,
+        proof: ShuffleProof,
     ) -> Result<(), GameError> {
         self.room_mut(room_id)?
-            .submit_shuffled(new_cards)
+            .submit_shuffled(new_cards, proof)
             .map_err(Into::into)
     }
 
@@ -154,12 +375,25 @@ impl Lobby {
     pub fn submit_reveal_part(
         &mut self,
         room_id: RoomId,
-        card: CryptoHash,
+        part: RevealPart,
+        proof: RevealProof,
     ) -> Result<(), GameError> {
         self.room_mut(room_id)?
-            .submit_reveal_part(card)
+            .submit_reveal_part(part, proof)
             .map_err(Into::into)
     }
+
+    /// Reveal the secret key used in the last hand of the room so it can be audited.
+    /// Players still seated should provide `public_key`, a new key for the next hands.
+    pub fn submit_secret_key(
+        &mut self,
+        room_id: RoomId,
+        secret_key: SecretKey,
+        public_key: Option<PublicKey>,
+    ) -> Result<(), GameError> {
+        self.room_mut(room_id)?
+            .submit_secret_key(secret_key, public_key)
+    }
 }
 
 /// Poker interface for Lobby
@@ -168,4 +402,294 @@ impl Lobby {
     pub fn submit_bet_action(&mut self, room_id: RoomId, bet: BetAction) -> Result<(), GameError> {
         self.room_mut(room_id)?.submit_bet_action(bet)
     }
+
+    /// Decide whether to straddle, in rooms that allow straddles.
+    pub fn submit_straddle(&mut self, room_id: RoomId, straddle: bool) -> Result<(), GameError> {
+        self.room_mut(room_id)?.submit_straddle(straddle)
+    }
+
+    pub fn submit_showdown_action(
+        &mut self,
+        room_id: RoomId,
+        action: ShowdownAction,
+    ) -> Result<(), GameError> {
+        self.room_mut(room_id)?.submit_showdown_action(action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bigint::U256;
+    use crate::config::Currency;
+    use crate::deck::DeckError;
+    use crate::game::tests::config;
+    use crate::sra::{self, GENERATOR};
+    use crate::test_utils::{set_call, set_callback, set_deposit, set_signer};
+
+    fn public_key(secret_key: u64) -> PublicKey {
+        PublicKey::Sra(sra::pow_mod(&GENERATOR, &U256::from(secret_key)))
+    }
+
+    /// Lobby with a room hosted by alice.
+    fn lobby_with(config: TableConfig) -> Lobby {
+        set_signer("alice");
+        let mut lobby = Lobby::new();
+        lobby
+            .new_room("room".to_string(), Some(config), None)
+            .unwrap();
+        lobby
+    }
+
+    /// Lobby with a room hosted by alice, where tokens are worth `chip_value`.
+    fn lobby(chip_value: Balance) -> Lobby {
+        lobby_with(TableConfig {
+            chip_value,
+            ..config()
+        })
+    }
+
+    /// Lobby with a room hosted by alice, where tokens are bought with the fungible token
+    /// `token`.
+    fn token_lobby() -> Lobby {
+        lobby_with(TableConfig {
+            currency: Currency::FungibleToken {
+                account_id: "token".to_string(),
+                decimals: 18,
+            },
+            ..config()
+        })
+    }
+
+    /// Call `ft_on_transfer` from `token_account_id`, as `ft_transfer_call` does.
+    fn transfer_call(
+        lobby: &mut Lobby,
+        token_account_id: &str,
+        sender_id: &str,
+        amount: Balance,
+        msg: &str,
+    ) -> String {
+        set_signer(token_account_id);
+        lobby.ft_on_transfer(sender_id.to_string(), amount.to_string(), msg.to_string())
+    }
+
+    fn enter_msg(room_id: RoomId) -> String {
+        serde_json::json!({ "room_id": room_id, "public_key": public_key(3) }).to_string()
+    }
+
+    /// Enter room 0 as `account_id` attaching `deposit`. Return the balance of the
+    /// contract after refunds.
+    fn enter(lobby: &mut Lobby, account_id: &str, deposit: Balance) -> Result<Balance, GameError> {
+        set_deposit(account_id, deposit);
+        lobby.enter(0, public_key(3), None)?;
+        Ok(env::account_balance())
+    }
+
+    #[test]
+    fn buy_in_within_limits() {
+        let chip_value = TableConfig::default().chip_value;
+        let mut lobby = lobby(chip_value);
+
+        for tokens in [99, 1001].iter() {
+            set_deposit("bob", tokens * chip_value);
+            assert!(matches!(
+                lobby.enter(0, public_key(3), None),
+                Err(GameError::InvalidBuyIn)
+            ));
+            assert_eq!(env::account_balance(), 0);
+        }
+
+        assert_eq!(
+            enter(&mut lobby, "bob", 100 * chip_value).unwrap(),
+            100 * chip_value
+        );
+        assert_eq!(
+            enter(&mut lobby, "carol", 1000 * chip_value).unwrap(),
+            1000 * chip_value
+        );
+        assert_eq!(lobby.room_ref(0).unwrap().poker_state().tokens(1), 1000);
+    }
+
+    #[test]
+    fn buy_in_whole_tokens() {
+        let chip_value = 1_000_000;
+        let mut lobby = lobby(chip_value);
+
+        set_deposit("bob", 250 * chip_value + 1);
+        assert!(matches!(
+            lobby.enter(0, public_key(3), None),
+            Err(GameError::InvalidBuyIn)
+        ));
+        assert_eq!(env::account_balance(), 0);
+
+        enter(&mut lobby, "bob", 250 * chip_value).unwrap();
+        assert_eq!(lobby.poker_state(0).unwrap().tokens(0), 250);
+        assert_eq!(
+            lobby.escrowed_balance(0, "bob".to_string()).unwrap(),
+            (250 * chip_value).to_string()
+        );
+
+        // Leaving pays back the value of the tokens.
+        set_deposit("bob", 250 * chip_value);
+        lobby.leave(0).unwrap();
+        assert_eq!(env::account_balance(), 0);
+    }
+
+    #[test]
+    fn refund_when_entering_fails() {
+        let chip_value = TableConfig::default().chip_value;
+        let mut lobby = lobby(chip_value);
+
+        set_deposit("bob", 100 * chip_value);
+        assert!(matches!(
+            lobby.enter(1, public_key(3), None),
+            Err(GameError::RoomIdNotFound)
+        ));
+        assert_eq!(env::account_balance(), 0);
+
+        enter(&mut lobby, "bob", 100 * chip_value).unwrap();
+        assert!(matches!(
+            enter(&mut lobby, "bob", 100 * chip_value),
+            Err(GameError::DeckError(DeckError::PlayerAlreadyInGame))
+        ));
+        assert_eq!(env::account_balance(), 0);
+    }
+
+    #[test]
+    fn calls_from_contracts_act_as_the_contract() {
+        let chip_value = TableConfig::default().chip_value;
+        let mut lobby = lobby(chip_value);
+
+        // The seat belongs to the contract that attached the deposit, not to the signer.
+        set_call("alice", "wallet", 100 * chip_value);
+        lobby.enter(0, public_key(3), None).unwrap();
+
+        set_signer("alice");
+        assert!(matches!(lobby.ready(0), Err(GameError::NotAPlayer)));
+        set_call("alice", "wallet", 100 * chip_value);
+        lobby.ready(0).unwrap();
+        lobby.leave(0).unwrap();
+        assert_eq!(env::account_balance(), 0);
+    }
+
+    #[test]
+    fn failed_near_payout_can_be_withdrawn() {
+        let chip_value = TableConfig::default().chip_value;
+        let mut lobby = lobby(chip_value);
+        let amount = 100 * chip_value;
+        enter(&mut lobby, "bob", amount).unwrap();
+        set_signer("bob");
+        lobby.leave(0).unwrap();
+
+        set_callback(PromiseResult::Successful(vec![]));
+        lobby.on_near_payout(0, "bob".to_string(), amount.to_string());
+        assert_eq!(lobby.escrowed_balance(0, "bob".to_string()).unwrap(), "0");
+
+        set_callback(PromiseResult::Failed);
+        lobby.on_near_payout(0, "bob".to_string(), amount.to_string());
+        assert_eq!(
+            lobby.escrowed_balance(0, "bob".to_string()).unwrap(),
+            amount.to_string()
+        );
+
+        set_deposit("bob", amount);
+        lobby.withdraw(0).unwrap();
+        assert_eq!(env::account_balance(), 0);
+        assert!(matches!(
+            lobby.withdraw(0),
+            Err(GameError::NothingToWithdraw)
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the contract can call this method.")]
+    fn payout_callbacks_are_private() {
+        let mut lobby = lobby(TableConfig::default().chip_value);
+        set_signer("bob");
+        lobby.on_near_payout(0, "bob".to_string(), "1".to_string());
+    }
+
+    #[test]
+    fn ft_on_transfer_enters_the_room() {
+        let chip_value = TableConfig::default().chip_value;
+        let mut lobby = token_lobby();
+        let amount = 100 * chip_value;
+
+        assert_eq!(
+            transfer_call(&mut lobby, "token", "bob", amount, &enter_msg(0)),
+            "0"
+        );
+        assert_eq!(
+            lobby.escrowed_balance(0, "bob".to_string()).unwrap(),
+            amount.to_string()
+        );
+        assert_eq!(lobby.poker_state(0).unwrap().tokens(0), 100);
+    }
+
+    #[test]
+    fn ft_on_transfer_refunds_when_entering_fails() {
+        let chip_value = TableConfig::default().chip_value;
+        let mut token_lobby = token_lobby();
+        let amount = 100 * chip_value;
+        let refund = amount.to_string();
+
+        // Another token, a malformed message, a missing room or an invalid buy-in.
+        assert_eq!(
+            transfer_call(&mut token_lobby, "fake", "bob", amount, &enter_msg(0)),
+            refund
+        );
+        assert_eq!(
+            transfer_call(&mut token_lobby, "token", "bob", amount, "{}"),
+            refund
+        );
+        assert_eq!(
+            transfer_call(&mut token_lobby, "token", "bob", amount, &enter_msg(1)),
+            refund
+        );
+        assert_eq!(
+            transfer_call(&mut token_lobby, "token", "bob", amount + 1, &enter_msg(0)),
+            (amount + 1).to_string()
+        );
+        assert_eq!(
+            token_lobby.escrowed_balance(0, "bob".to_string()).unwrap(),
+            "0"
+        );
+
+        // NEAR can't be used in the room, and tokens can't be used in NEAR rooms.
+        set_deposit("bob", amount);
+        assert!(matches!(
+            token_lobby.enter(0, public_key(3), None),
+            Err(GameError::InvalidCurrency)
+        ));
+        assert_eq!(env::account_balance(), 0);
+        let mut lobby = lobby(chip_value);
+        assert_eq!(
+            transfer_call(&mut lobby, "token", "bob", amount, &enter_msg(0)),
+            refund
+        );
+    }
+
+    #[test]
+    fn failed_ft_payout_can_be_withdrawn() {
+        let amount = 100 * TableConfig::default().chip_value;
+        let mut lobby = token_lobby();
+        transfer_call(&mut lobby, "token", "bob", amount, &enter_msg(0));
+        set_signer("bob");
+        lobby.leave(0).unwrap();
+        assert_eq!(lobby.escrowed_balance(0, "bob".to_string()).unwrap(), "0");
+
+        set_callback(PromiseResult::Failed);
+        lobby.on_ft_payout(0, "bob".to_string(), amount.to_string());
+        assert_eq!(
+            lobby.escrowed_balance(0, "bob".to_string()).unwrap(),
+            amount.to_string()
+        );
+
+        set_signer("bob");
+        lobby.withdraw(0).unwrap();
+        assert_eq!(lobby.escrowed_balance(0, "bob".to_string()).unwrap(), "0");
+        set_callback(PromiseResult::Successful(vec![]));
+        lobby.on_ft_payout(0, "bob".to_string(), amount.to_string());
+        assert_eq!(lobby.escrowed_balance(0, "bob".to_string()).unwrap(), "0");
+    }
 }
@@ -1,5 +1,7 @@
+use crate::config::{AnteType, BlindLevel, LevelDuration, TableConfig, TimeBank};
+use crate::hand::{evaluate, Card, HandValue, BOARD_SIZE};
+use crate::pot::{settle, Settlement};
 use crate::types::CardId;
-use crate::types::CryptoHash;
 use crate::types::PlayerId;
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
@@ -35,6 +37,10 @@ impl Stage {
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Clone, Eq, PartialEq)]
 pub enum PokerStatus {
     Idle,
+    /// Waiting for `player_id`, next to the big blind, to decide whether to straddle.
+    StraddleDecision {
+        player_id: PlayerId,
+    },
     Dealing {
         player_id: PlayerId,
         card_id: CardId,
@@ -43,12 +49,12 @@ pub enum PokerStatus {
     Betting {
         // Waiting for player `target` to make an action.
         target: PlayerId,
-        // Last player to raise. If raised is false this is big blind.
-        until: PlayerId,
-        // If some player have raised in this round.
-        raised: bool,
         // Current max stake that must be called or raised
         max_stake: u64,
+        // Minimum amount to increase max stake on a raise. Size of the last bet or raise.
+        min_raise: u64,
+        // Number of bets and raises in this round. Blinds count as a bet.
+        bets: u64,
         // Next stage to play.
         next_stage: Stage,
     },
@@ -57,6 +63,10 @@ pub enum PokerStatus {
         card_id: CardId,
         missing_to_reveal: u8,
     },
+    /// Waiting for `player_id` to decide whether to show or muck its cards.
+    ShowdownDecision {
+        player_id: PlayerId,
+    },
     Showdown {
         player_id: PlayerId,
         card_id: CardId,
@@ -72,14 +82,65 @@ pub enum PokerError {
     TooLowStake,
     NotEnoughStake,
 
+    /// Tried to check, but there is a higher stake that must be called.
+    CannotCheck,
+    /// Tried to call, but player stake already matches the max stake.
+    NothingToCall,
+    /// Tried to bet, but a bet was already placed in this round. Raise instead.
+    CannotBet,
+    /// Tried to raise, but no bet was placed in this round. Bet instead.
+    CannotRaise,
+    /// Tried to raise, but betting was not reopened after the last action of this player.
+    /// Happens when the stake was increased by an all-in lower than a full raise.
+    CannotReraise,
+    /// Stake is higher than the allowed by the betting structure.
+    TooHighStake,
+    /// No more raises are allowed in this betting round.
+    BettingCapReached,
+
     NotBettingRound,
     NotBettingTurn,
+
+    /// Tried to show or muck cards, but it is not the turn of this player at showdown.
+    NotShowdownTurn,
+    /// Tried to straddle, but this player is not deciding whether to straddle.
+    NotStraddleTurn,
 }
 
+/// Limits on the size of bets and raises.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub enum BettingStructure {
+    /// Raise at least the size of the previous bet or raise. No upper limit.
+    NoLimit,
+    /// Raise at least the size of the previous bet or raise, and at most the size of the pot.
+    PotLimit,
+    /// Bets and raises are equal to the big blind on the first two betting rounds, and
+    /// twice the big blind on the last two. Up to `FIXED_LIMIT_CAP` bets per round.
+    FixedLimit,
+}
+
+impl Default for BettingStructure {
+    fn default() -> Self {
+        Self::NoLimit
+    }
+}
+
+/// Maximum number of bets and raises in a betting round with fixed limit.
+const FIXED_LIMIT_CAP: u64 = 4;
+
 #[derive(Serialize, Deserialize)]
 pub enum BetAction {
     Fold,
-    Stake(u64),
+    /// Pass without staking more tokens. Only allowed if there is nothing to call.
+    Check,
+    /// Match the max stake, or stake all tokens if there are not enough.
+    Call,
+    /// Open the betting round staking `amount` tokens over the current max stake.
+    Bet(u64),
+    /// Increase the total stake of the player in this turn TO the amount.
+    Raise(u64),
+    /// Stake all available tokens.
+    AllIn,
 }
 
 pub struct ActionResponse {
@@ -87,53 +148,155 @@ pub struct ActionResponse {
     pub action: BetAction,
 }
 
-// TODO: Remove automatically from the game players with 0 tokens.
-//       Mainly regarding card signatures.
+/// Blinds currently used and blinds used after current level is over.
+#[derive(Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct BlindLevelInfo {
+    /// Index of the current level.
+    pub level: u64,
+    pub current: BlindLevel,
+    /// None if current level is the last one.
+    pub next: Option<BlindLevel>,
+    /// Number of hands finished on the current level.
+    pub hands_on_level: u64,
+    /// Block timestamp when current level started. None if no hand was started yet.
+    pub started_at: Option<u64>,
+    /// Block timestamp when current level is over. None if level is measured in hands.
+    pub ends_at: Option<u64>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub enum ShowdownAction {
+    /// Reveal cards to all players.
+    Show,
+    /// Give up the pot without revealing cards.
+    Muck,
+}
+
+/// Actions available to the player that should bet.
+#[derive(Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct LegalActions {
+    pub player_id: PlayerId,
+    /// Folding is always allowed.
+    pub can_fold: bool,
+    pub can_check: bool,
+    /// Tokens to add to match the max stake, capped by available tokens. None if can't call.
+    pub call: Option<u64>,
+    /// True if nobody has bet in this round, so increasing the stake is a `Bet` rather
+    /// than a `Raise`.
+    pub is_bet: bool,
+    /// Minimum total stake for a full bet or raise. None if the player can't raise.
+    pub min_raise: Option<u64>,
+    /// Maximum total stake for a bet or raise. None if the player can't raise.
+    pub max_raise: Option<u64>,
+    /// Total stake if going all-in. None if going all-in is not allowed.
+    pub all_in: Option<u64>,
+}
 
 /// Raw poker implementation.
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
 pub struct Poker {
     /// Number of tokens each player has available.
     tokens: Vec<u64>,
+    /// Extra time each player can use when its action clock runs out.
+    time_bank: Vec<u64>,
     /// Currently staked tokens.
     staked: Vec<u64>,
+    /// Tokens put in the pot as antes in this turn. They are not part of the stake.
+    antes: Vec<u64>,
     /// Players that have already folded its cards in this turn.
     folded: Vec<bool>,
+    /// Players that have staked all their tokens in this turn. They can't make more bets.
+    all_in: Vec<bool>,
+    /// Players that have made an action since the last raise in the current betting round.
+    acted: Vec<bool>,
+    /// Last player to bet or raise in the current betting round.
+    last_aggressor: Option<PlayerId>,
+    /// Players that reached the showdown in the order they acted, and whether they
+    /// showed or mucked their cards.
+    showdown: Vec<(PlayerId, ShowdownAction)>,
     /// Current status.
     pub status: PokerStatus,
-    /// Number of token used for the big blind. This value will double on each game.
-    blind_token: u64,
-    /// Player which is the big blind on next round.
+    /// How the time bank of each player is refilled. None if there is no time bank.
+    time_bank_config: Option<TimeBank>,
+    /// Number of hands finished in this room.
+    hands_played: u64,
+    /// Blinds used on each level.
+    blind_levels: Vec<BlindLevel>,
+    /// Who puts the ante on each hand.
+    ante_type: AnteType,
+    /// Player next to the big blind can put twice the big blind before the cards are dealt.
+    straddle: bool,
+    /// Player that have straddled in this turn.
+    straddler: Option<PlayerId>,
+    /// Index of the current blind level.
+    level: u64,
+    /// Number of hands finished on the current blind level.
+    hands_on_level: u64,
+    /// Block timestamp when current blind level started. None if no hand was started yet.
+    level_started_at: Option<u64>,
+    /// Limits on the size of bets and raises.
+    betting_structure: BettingStructure,
+    /// Seat of the dealer button in this round. None if no round was started yet.
+    /// When the player with the button is eliminated, the button stays on the previous
+    /// seat, which keeps the order of the remaining players (dead button).
+    button: Option<PlayerId>,
+    /// Seat of the small blind in this round. On heads-up this is the button.
+    small_blind: PlayerId,
+    /// The small blind is not posted in this round, since the player that should post it
+    /// was eliminated on the previous round (dead small blind).
+    dead_small_blind: bool,
+    /// Player which is the big blind in this round.
     big_blind: PlayerId,
     /// Card on the top of the stack.
     first_unrevealed_card: CardId,
+    /// Pots and payouts of the last round played.
+    last_settlement: Option<Settlement>,
 }
 
 impl Poker {
-    pub fn new() -> Self {
+    pub fn new(config: &TableConfig) -> Self {
         Self {
             tokens: vec![],
+            time_bank: vec![],
             staked: vec![],
+            antes: vec![],
             folded: vec![],
+            all_in: vec![],
+            acted: vec![],
+            last_aggressor: None,
+            showdown: vec![],
             status: PokerStatus::Idle,
-            blind_token: 6,
+            time_bank_config: config.time_bank.clone(),
+            hands_played: 0,
+            blind_levels: config.blind_levels.clone(),
+            ante_type: config.ante_type.clone(),
+            straddle: config.straddle,
+            straddler: None,
+            level: 0,
+            hands_on_level: 0,
+            level_started_at: None,
+            betting_structure: config.betting_structure.clone(),
+            button: None,
+            small_blind: 0,
+            dead_small_blind: false,
             big_blind: 0,
             first_unrevealed_card: 0,
+            last_settlement: None,
         }
     }
 
     pub fn new_player(&mut self, tokens: u64) {
         self.tokens.push(tokens);
+        self.time_bank.push(
+            self.time_bank_config
+                .as_ref()
+                .map_or(0, |time_bank| time_bank.initial),
+        );
         self.staked.push(0);
+        self.antes.push(0);
         self.folded.push(false);
-    }
-
-    fn prev_player(&self, player_id: PlayerId) -> PlayerId {
-        if player_id == 0 {
-            self.num_players() - 1
-        } else {
-            player_id - 1
-        }
+        self.all_in.push(false);
+        self.acted.push(false);
     }
 
     fn next_player(&self, player_id: PlayerId) -> PlayerId {
@@ -144,6 +307,128 @@ impl Poker {
         }
     }
 
+    /// Tokens `player_id` has, including the ones staked in the current round.
+    pub fn tokens(&self, player_id: PlayerId) -> u64 {
+        self.tokens[player_id as usize]
+    }
+
+    /// Take all tokens from `player_id` to pay them back. Should only be called between rounds.
+    pub fn cash_out(&mut self, player_id: PlayerId) -> u64 {
+        assert!(
+            self.status == PokerStatus::Idle,
+            "Tokens can only be cashed out between rounds."
+        );
+        std::mem::replace(&mut self.tokens[player_id as usize], 0)
+    }
+
+    /// Extra time `player_id` can use when its action clock runs out.
+    pub fn time_bank(&self, player_id: PlayerId) -> u64 {
+        self.time_bank[player_id as usize]
+    }
+
+    /// Consume `time` from the time bank of `player_id`.
+    pub fn use_time_bank(&mut self, player_id: PlayerId, time: u64) {
+        let time_bank = &mut self.time_bank[player_id as usize];
+        *time_bank = time_bank.saturating_sub(time);
+    }
+
+    /// Add time to the time banks if enough hands were played since the last top up.
+    fn top_up_time_banks(&mut self) {
+        if let Some(config) = &self.time_bank_config {
+            if self.hands_played % config.every_hands == 0 {
+                for time_bank in self.time_bank.iter_mut() {
+                    *time_bank =
+                        std::cmp::min(time_bank.saturating_add(config.top_up), config.initial);
+                }
+            }
+        }
+    }
+
+    /// Players without tokens at the end of the round, which should be eliminated.
+    pub fn busted_players(&self) -> Vec<PlayerId> {
+        (0..self.num_players())
+            .filter(|&player_id| self.tokens[player_id as usize] == 0)
+            .collect()
+    }
+
+    /// Remove `player_id` from the table. Players after it move one seat down.
+    /// Button and blinds on the removed seat move to the previous seat, so the order
+    /// of the remaining players is kept. Should only be called between rounds.
+    pub fn remove_player(&mut self, player_id: PlayerId) {
+        assert!(
+            self.status == PokerStatus::Idle,
+            "Players can only be removed between rounds."
+        );
+
+        let index = player_id as usize;
+        self.tokens.remove(index);
+        self.time_bank.remove(index);
+        self.staked.remove(index);
+        self.antes.remove(index);
+        self.folded.remove(index);
+        self.all_in.remove(index);
+        self.acted.remove(index);
+
+        let num_players = self.num_players();
+        if num_players == 0 {
+            self.button = None;
+            return;
+        }
+
+        if self.big_blind == player_id {
+            // Player that should post the small blind next round is eliminated.
+            self.dead_small_blind = true;
+        }
+
+        let remap = |seat: PlayerId| {
+            if seat > player_id {
+                seat - 1
+            } else if seat == player_id {
+                (seat + num_players - 1) % num_players
+            } else {
+                seat
+            }
+        };
+
+        self.button = self.button.map(remap);
+        self.small_blind = remap(self.small_blind);
+        self.big_blind = remap(self.big_blind);
+    }
+
+    fn button(&self) -> PlayerId {
+        self.button.expect("Round not started.")
+    }
+
+    /// Move the button, small blind and big blind for a new round.
+    /// The big blind moves to the next player. Small blind and button follow it to the
+    /// seats used by the big blind and small blind on the previous round, even if the
+    /// players on those seats were eliminated (dead button). On heads-up the button
+    /// is the small blind.
+    fn move_button(&mut self) {
+        let heads_up = self.num_players() == 2;
+
+        match self.button {
+            None => {
+                self.button = Some(0);
+                self.small_blind = if heads_up { 0 } else { 1 };
+                self.big_blind = self.next_player(self.small_blind);
+                self.dead_small_blind = false;
+            }
+            Some(_) => {
+                let big_blind = self.next_player(self.big_blind);
+                if heads_up {
+                    self.small_blind = self.next_player(big_blind);
+                    self.button = Some(self.small_blind);
+                    self.dead_small_blind = false;
+                } else {
+                    self.button = Some(self.small_blind);
+                    self.small_blind = self.big_blind;
+                }
+                self.big_blind = big_blind;
+            }
+        }
+    }
+
     fn next_on_game(&self, mut player_id: PlayerId) -> PlayerId {
         for _ in 0..self.num_players() {
             if !self.folded[player_id as usize] {
@@ -160,23 +445,255 @@ impl Poker {
         self.folded.iter().filter(|&folded| *folded).count() as u64
     }
 
+    /// Tokens player can stake in this turn. Antes are not available.
+    fn stack(&self, player_id: PlayerId) -> u64 {
+        self.tokens[player_id as usize] - self.antes[player_id as usize]
+    }
+
     /// Increase the stake for player_id to stake. If stake is less than current staked
     /// by this player, it will return Error without changing anything.
-    /// If stake is bigger than total tokens, it will stake all tokens.
+    /// If stake is bigger than total tokens, it will stake all tokens and the player
+    /// will be all-in.
     fn try_stake(&mut self, player_id: PlayerId, stake: u64) -> Result<(), PokerError> {
-        let total = self
-            .tokens
-            .get(player_id as usize)
-            .ok_or(PokerError::InvalidPlayerId)?;
+        if player_id >= self.num_players() {
+            return Err(PokerError::InvalidPlayerId);
+        }
+
+        let total = self.stack(player_id);
 
         if stake < self.staked[player_id as usize] {
             return Err(PokerError::TooLowStake);
         }
 
-        self.staked[player_id as usize] = std::cmp::min(stake, *total);
+        self.staked[player_id as usize] = std::cmp::min(stake, total);
+        self.all_in[player_id as usize] = self.staked[player_id as usize] == total;
         Ok(())
     }
 
+    /// Put `ante` tokens in the pot from player_id, or all its tokens if there are not enough.
+    fn post_ante(&mut self, player_id: PlayerId, ante: u64) {
+        let available = self.stack(player_id) - self.staked[player_id as usize];
+        self.antes[player_id as usize] += std::cmp::min(ante, available);
+        self.all_in[player_id as usize] = self.staked[player_id as usize] == self.stack(player_id);
+    }
+
+    /// Highest stake placed by any player in this turn.
+    fn max_stake(&self) -> u64 {
+        self.staked.iter().cloned().max().unwrap_or(0)
+    }
+
+    /// Player is still in the game and is able to bet.
+    fn can_bet(&self, player_id: PlayerId) -> bool {
+        !self.folded[player_id as usize] && !self.all_in[player_id as usize]
+    }
+
+    /// Player should make an action before the current betting round is over.
+    /// If all other players are all-in or folded, it only needs to match the max stake.
+    fn should_bet(&self, player_id: PlayerId, max_stake: u64) -> bool {
+        if !self.can_bet(player_id) {
+            return false;
+        }
+
+        let others_can_bet =
+            (0..self.num_players()).any(|other| other != player_id && self.can_bet(other));
+
+        self.staked[player_id as usize] < max_stake
+            || (others_can_bet && !self.acted[player_id as usize])
+    }
+
+    /// First player starting at `player_id` (inclusive) that should make an action
+    /// in the current betting round. None if the betting round is over.
+    fn next_to_bet(&self, mut player_id: PlayerId, max_stake: u64) -> Option<PlayerId> {
+        for _ in 0..self.num_players() {
+            if self.should_bet(player_id, max_stake) {
+                return Some(player_id);
+            }
+            player_id = self.next_player(player_id);
+        }
+        None
+    }
+
+    /// Start a betting round where `first` is the first player to act.
+    /// If no player is able to bet, move directly to the next stage.
+    fn start_betting(&mut self, first: PlayerId, next_stage: Stage, min_raise: u64, bets: u64) {
+        self.acted = vec![false; self.num_players() as usize];
+        self.last_aggressor = None;
+        self.update_betting(first, next_stage, min_raise, bets);
+    }
+
+    /// Player `player_id` has finished its action in the current betting round.
+    /// Move the turn to the next player, or to the next stage if the round is over.
+    fn continue_betting(
+        &mut self,
+        player_id: PlayerId,
+        next_stage: Stage,
+        min_raise: u64,
+        bets: u64,
+    ) {
+        self.acted[player_id as usize] = true;
+        self.update_betting(self.next_player(player_id), next_stage, min_raise, bets);
+    }
+
+    fn update_betting(&mut self, from: PlayerId, next_stage: Stage, min_raise: u64, bets: u64) {
+        let max_stake = self.max_stake();
+
+        match self.next_to_bet(from, max_stake) {
+            Some(target) => {
+                self.status = PokerStatus::Betting {
+                    target,
+                    max_stake,
+                    min_raise,
+                    bets,
+                    next_stage,
+                }
+            }
+            None => self.start_stage(next_stage),
+        }
+    }
+
+    /// Blinds for the current hand.
+    fn blinds(&self) -> &BlindLevel {
+        &self.blind_levels[self.level as usize]
+    }
+
+    /// Move to the next blind levels if the current level is over at `timestamp`.
+    /// Should be called before starting a new hand.
+    pub fn update_blind_level(&mut self, timestamp: u64) {
+        let mut started_at = match self.level_started_at {
+            Some(started_at) => started_at,
+            None => {
+                // First hand of the game.
+                self.level_started_at = Some(timestamp);
+                return;
+            }
+        };
+
+        while (self.level as usize) + 1 < self.blind_levels.len() {
+            match self.blinds().duration {
+                LevelDuration::Hands(hands) if self.hands_on_level >= hands => {
+                    started_at = timestamp;
+                }
+                LevelDuration::Time(time) if started_at.saturating_add(time) <= timestamp => {
+                    started_at += time;
+                }
+                _ => break,
+            }
+
+            self.level += 1;
+            self.hands_on_level = 0;
+        }
+
+        self.level_started_at = Some(started_at);
+    }
+
+    pub fn blind_level_info(&self) -> BlindLevelInfo {
+        let current = self.blinds().clone();
+
+        BlindLevelInfo {
+            level: self.level,
+            ends_at: match current.duration {
+                LevelDuration::Time(time) => self
+                    .level_started_at
+                    .map(|start| start.saturating_add(time)),
+                LevelDuration::Hands(_) => None,
+            },
+            current,
+            next: self.blind_levels.get(self.level as usize + 1).cloned(),
+            hands_on_level: self.hands_on_level,
+            started_at: self.level_started_at,
+        }
+    }
+
+    /// Total tokens staked by all players in this turn, including antes.
+    fn pot(&self) -> u64 {
+        self.staked.iter().sum::<u64>() + self.antes.iter().sum::<u64>()
+    }
+
+    /// Minimum and maximum total stake `player_id` can raise to in the current betting round.
+    /// The player can always stake less than the minimum going all-in.
+    fn raise_limits(
+        &self,
+        player_id: PlayerId,
+        max_stake: u64,
+        min_raise: u64,
+        bets: u64,
+        next_stage: &Stage,
+    ) -> Result<(u64, u64), PokerError> {
+        if self.acted[player_id as usize] {
+            return Err(PokerError::CannotReraise);
+        }
+
+        match self.betting_structure {
+            BettingStructure::NoLimit => Ok((max_stake + min_raise, u64::MAX)),
+            BettingStructure::PotLimit => {
+                let to_call = max_stake - self.staked[player_id as usize];
+                Ok((max_stake + min_raise, max_stake + self.pot() + to_call))
+            }
+            BettingStructure::FixedLimit => {
+                if bets >= FIXED_LIMIT_CAP {
+                    return Err(PokerError::BettingCapReached);
+                }
+                let size = match next_stage {
+                    Stage::Flop | Stage::Turn => self.blinds().big_blind,
+                    Stage::River | Stage::Showdown => 2 * self.blinds().big_blind,
+                };
+                Ok((max_stake + size, max_stake + size))
+            }
+        }
+    }
+
+    /// Actions available to the player that should bet. None if not in a betting round.
+    pub fn legal_actions(&self) -> Option<LegalActions> {
+        if let PokerStatus::Betting {
+            target,
+            max_stake,
+            min_raise,
+            bets,
+            next_stage,
+        } = &self.status
+        {
+            let staked = self.staked[*target as usize];
+            let total = self.stack(*target);
+
+            let limits = if total > *max_stake {
+                self.raise_limits(*target, *max_stake, *min_raise, *bets, next_stage)
+                    .ok()
+            } else {
+                None
+            };
+
+            let (min_raise, max_raise) = match limits {
+                Some((min_stake, max_allowed)) if min_stake <= total => {
+                    (Some(min_stake), Some(std::cmp::min(max_allowed, total)))
+                }
+                _ => (None, None),
+            };
+
+            let all_in = match limits {
+                _ if total <= *max_stake => Some(total),
+                Some((_, max_allowed)) if total <= max_allowed => Some(total),
+                _ => None,
+            };
+
+            Some(LegalActions {
+                player_id: *target,
+                can_fold: true,
+                can_check: staked >= *max_stake,
+                call: if staked < *max_stake {
+                    Some(std::cmp::min(*max_stake, total) - staked)
+                } else {
+                    None
+                },
+                is_bet: *bets == 0,
+                min_raise,
+                max_raise,
+                all_in,
+            })
+        } else {
+            None
+        }
+    }
+
     pub fn get_status(&self) -> PokerStatus {
         self.status.clone()
     }
@@ -184,6 +701,8 @@ impl Poker {
     pub fn get_turn(&self) -> Option<PlayerId> {
         match self.status {
             PokerStatus::Betting { target, .. } => Some(target),
+            PokerStatus::ShowdownDecision { player_id } => Some(player_id),
+            PokerStatus::StraddleDecision { player_id } => Some(player_id),
             _ => None,
         }
     }
@@ -194,23 +713,55 @@ impl Poker {
         self.first_unrevealed_card - 1
     }
 
+    /// Cards are dealt two at a time to each player, starting left of the button.
     fn card_id_from_player(&self, player_id: PlayerId, first_card: bool) -> CardId {
-        2 * player_id + (!first_card as u64)
+        let first_dealt = self.next_player(self.button());
+        let position = (player_id + self.num_players() - first_dealt) % self.num_players();
+        2 * position + (!first_card as u64)
+    }
+
+    /// Start dealing the hole cards, starting left of the button.
+    fn deal(&mut self) {
+        self.status = PokerStatus::Dealing {
+            player_id: self.next_player(self.button()),
+            card_id: self.get_card(),
+            first_card: true,
+        };
     }
 
     pub fn next(&mut self) {
         match self.status.clone() {
             PokerStatus::Idle => {
-                // Make small blind and big blinds bet
-                self.try_stake(self.big_blind, self.blind_token).unwrap();
-                self.try_stake(self.prev_player(self.big_blind), self.blind_token / 2)
-                    .unwrap();
-
-                self.status = PokerStatus::Dealing {
-                    player_id: 0,
-                    card_id: self.get_card(),
-                    first_card: true,
-                };
+                self.showdown = vec![];
+                self.move_button();
+
+                // Put antes, then small blind and big blinds bet. With big blind ante, the big
+                // blind pays its blind before the ante.
+                let blinds = self.blinds().clone();
+
+                if self.ante_type == AnteType::Standard {
+                    for player_id in 0..self.num_players() {
+                        self.post_ante(player_id, blinds.ante);
+                    }
+                }
+
+                self.try_stake(self.big_blind, blinds.big_blind).unwrap();
+                if !self.dead_small_blind {
+                    self.try_stake(self.small_blind, blinds.small_blind)
+                        .unwrap();
+                }
+
+                if self.ante_type == AnteType::BigBlind {
+                    self.post_ante(self.big_blind, blinds.ante);
+                }
+
+                if self.straddle && self.num_players() > 2 {
+                    self.status = PokerStatus::StraddleDecision {
+                        player_id: self.next_player(self.big_blind),
+                    };
+                } else {
+                    self.deal();
+                }
             }
             PokerStatus::Dealing {
                 player_id,
@@ -224,19 +775,18 @@ impl Poker {
                         first_card: false,
                     };
                 } else {
-                    if player_id + 1 == self.num_players() {
+                    if player_id == self.button() {
                         // All cards where already dealt. Start first round of betting.
-                        let target = self.next_player(self.big_blind);
-                        self.status = PokerStatus::Betting {
-                            target,
-                            until: self.big_blind,
-                            raised: false,
-                            max_stake: self.blind_token,
-                            next_stage: Stage::Flop,
+                        // Straddle acts as the big blind, raises are at least twice the big blind.
+                        let big_blind = self.blinds().big_blind;
+                        let (last_blind, min_raise) = match self.straddler {
+                            Some(straddler) => (straddler, 2 * big_blind),
+                            None => (self.big_blind, big_blind),
                         };
+                        self.start_betting(self.next_player(last_blind), Stage::Flop, min_raise, 1);
                     } else {
                         self.status = PokerStatus::Dealing {
-                            player_id: player_id + 1,
+                            player_id: self.next_player(player_id),
                             card_id: self.get_card(),
                             first_card: true,
                         };
@@ -249,15 +799,14 @@ impl Poker {
                 ..
             } => {
                 if missing_to_reveal == 0 {
-                    // Find next player who have not folded after the big blind.
-                    let target = self.next_on_game(self.next_player(self.big_blind));
-                    self.status = PokerStatus::Betting {
-                        target,
-                        until: self.big_blind,
-                        raised: false,
-                        max_stake: self.blind_token,
-                        next_stage: stage.next(),
-                    };
+                    // Betting starts left of the button, with the next player who is able to bet.
+                    // If at most one player is able to bet the next stage is revealed directly.
+                    self.start_betting(
+                        self.next_player(self.button()),
+                        stage.next(),
+                        self.blinds().big_blind,
+                        0,
+                    );
                 } else {
                     self.status = PokerStatus::Revealing {
                         stage,
@@ -266,7 +815,6 @@ impl Poker {
                     };
                 }
             }
-            // TODO: Fix issue with infinite showdown loop
             PokerStatus::Showdown {
                 player_id,
                 first_card,
@@ -279,46 +827,144 @@ impl Poker {
                         first_card: false,
                     };
                 } else {
-                    let next_player = self.next_on_game(player_id);
-
-                    if next_player < player_id {
-                        // All cards were revealed
-                        self.status = PokerStatus::WaitingRevealedCards;
-                    } else {
-                        self.status = PokerStatus::Showdown {
-                            player_id: next_player,
-                            card_id: self.card_id_from_player(player_id, true),
-                            first_card: true,
-                        };
-                    }
+                    self.next_showdown_turn(player_id);
                 }
             }
             PokerStatus::Betting { .. } => panic!("Called next on betting state."),
+            PokerStatus::ShowdownDecision { .. } => {
+                panic!("Called next while waiting for showdown decision.")
+            }
+            PokerStatus::StraddleDecision { .. } => {
+                panic!("Called next while waiting for straddle decision.")
+            }
             PokerStatus::WaitingRevealedCards => {
                 panic!("Called next while waiting for revealed cards.")
             }
         }
     }
 
+    pub fn get_last_settlement(&self) -> Option<Settlement> {
+        self.last_settlement.clone()
+    }
+
+    /// Players in the order they receive odd chips from split pots.
+    /// It starts with the first player left of the button.
+    fn odd_chip_order(&self) -> Vec<PlayerId> {
+        let mut player_id = self.next_player(self.button());
+        let mut order = vec![];
+        for _ in 0..self.num_players() {
+            order.push(player_id);
+            player_id = self.next_player(player_id);
+        }
+        order
+    }
+
     /// Call when the round is over. Update the state of the game for the next round.
-    fn finish(&mut self, _winners: Vec<PlayerId>) {
+    /// `hands` is the value of the hand of each player, or None if it is unknown.
+    fn finish(&mut self, hands: Vec<Option<HandValue>>) {
+        let settlement = settle(
+            &self.staked,
+            &self.antes,
+            &self.folded,
+            &hands,
+            &self.odd_chip_order(),
+        );
+
+        for player_id in 0..self.num_players() as usize {
+            self.tokens[player_id] = self.tokens[player_id] - settlement.contributed[player_id]
+                + settlement.payouts[player_id];
+        }
+
+        self.last_settlement = Some(settlement);
+        self.hands_on_level += 1;
+        self.hands_played += 1;
+        self.top_up_time_banks();
+        self.reset_round();
+    }
+
+    /// Cancel the current round. Stakes and antes are given back to the players.
+    pub fn abort_round(&mut self) {
+        self.reset_round();
+    }
+
+    fn reset_round(&mut self) {
+        for player_id in 0..self.num_players() as usize {
+            self.staked[player_id] = 0;
+            self.antes[player_id] = 0;
+            self.folded[player_id] = false;
+            self.all_in[player_id] = false;
+        }
+
+        self.straddler = None;
+        self.dead_small_blind = false;
         self.status = PokerStatus::Idle;
-        self.big_blind = self.next_player(self.big_blind);
-        self.blind_token *= 2;
         self.first_unrevealed_card = 0;
+    }
+
+    /// Take all tokens from `player_id` and split them evenly between the other players.
+    /// Tokens that can't be split are given one by one starting left of `player_id`.
+    /// Should only be called between rounds.
+    pub fn slash(&mut self, player_id: PlayerId) {
+        assert!(
+            self.status == PokerStatus::Idle,
+            "Players can only be slashed between rounds."
+        );
+
+        let others = self.num_players() - 1;
+        if others == 0 {
+            return;
+        }
 
-        // TODO: Reassign stake to winners.
-        // TODO: Reset state
+        let tokens = std::mem::replace(&mut self.tokens[player_id as usize], 0);
+        let mut odd_tokens = tokens % others;
+        let mut other = self.next_player(player_id);
+
+        while other != player_id {
+            self.tokens[other as usize] += tokens / others;
+            if odd_tokens > 0 {
+                self.tokens[other as usize] += 1;
+                odd_tokens -= 1;
+            }
+            other = self.next_player(other);
+        }
+    }
+
+    /// Reveal the cards of `player_id` at showdown.
+    fn show_cards(&mut self, player_id: PlayerId) {
+        self.showdown.push((player_id, ShowdownAction::Show));
+        self.status = PokerStatus::Showdown {
+            player_id,
+            card_id: self.card_id_from_player(player_id, true),
+            first_card: true,
+        };
+    }
+
+    /// Player `player_id` already acted at showdown. Next player on game, clockwise,
+    /// should decide whether to show its cards. After all players acted the game waits for
+    /// the revealed cards.
+    fn next_showdown_turn(&mut self, player_id: PlayerId) {
+        let next_player = self.next_on_game(self.next_player(player_id));
+
+        if next_player == self.showdown[0].0 {
+            // All players at showdown acted.
+            self.status = PokerStatus::WaitingRevealedCards;
+        } else {
+            self.status = PokerStatus::ShowdownDecision {
+                player_id: next_player,
+            };
+        }
     }
 
     fn start_stage(&mut self, stage: Stage) {
         if stage == Stage::Showdown {
-            let player_id = self.next_on_game(0);
-            self.status = PokerStatus::Showdown {
-                player_id,
-                card_id: self.card_id_from_player(player_id, true),
-                first_card: true,
+            // Last player to bet or raise on the river shows first. If nobody did, the
+            // first player on game left of the button shows first.
+            // This player can't muck its cards.
+            let first = match self.last_aggressor {
+                Some(player_id) if !self.folded[player_id as usize] => player_id,
+                _ => self.next_on_game(self.next_player(self.button())),
             };
+            self.show_cards(first);
         } else {
             let missing_to_reveal = stage.cards_to_reveal() - 1;
             self.status = PokerStatus::Revealing {
@@ -329,16 +975,79 @@ impl Poker {
         }
     }
 
-    pub fn submit_revealed_cards(&mut self, _cards: Vec<Option<CryptoHash>>) {
+    /// Value of the best hand of `player_id` using its hole cards and the board.
+    /// None if the player folded or some of the cards are not revealed.
+    fn hand_value(&self, player_id: PlayerId, cards: &[Option<u64>]) -> Option<HandValue> {
+        if self.folded[player_id as usize] {
+            return None;
+        }
+
+        let first_board_card = 2 * self.num_players();
+
+        let hand = vec![
+            self.card_id_from_player(player_id, true),
+            self.card_id_from_player(player_id, false),
+        ]
+        .into_iter()
+        .chain(first_board_card..first_board_card + BOARD_SIZE)
+        .map(|card_id| cards.get(card_id as usize)?.and_then(Card::new))
+        .collect::<Option<Vec<_>>>()?;
+
+        Some(evaluate(&hand))
+    }
+
+    pub fn submit_revealed_cards(&mut self, cards: Vec<Option<u64>>) {
         if self.status != PokerStatus::WaitingRevealedCards {
             panic!("Not waiting revealed cards");
         }
 
-        // TODO: Find winners from cards revealed.
+        let hands = (0..self.num_players())
+            .map(|player_id| self.hand_value(player_id, &cards))
+            .collect();
+        self.finish(hands);
+    }
 
-        self.finish(vec![]);
+    /// Submit whether the player that is its turn at showdown shows or mucks its cards.
+    /// Mucking is the same as folding, the player gives up all the pots.
+    pub fn submit_showdown_action(
+        &mut self,
+        player_id: PlayerId,
+        action: ShowdownAction,
+    ) -> Result<(), PokerError> {
+        match self.status {
+            PokerStatus::ShowdownDecision { player_id: turn } if turn == player_id => {
+                match action {
+                    ShowdownAction::Show => self.show_cards(player_id),
+                    ShowdownAction::Muck => {
+                        self.folded[player_id as usize] = true;
+                        self.showdown.push((player_id, ShowdownAction::Muck));
+                        self.next_showdown_turn(player_id);
+                    }
+                }
+                Ok(())
+            }
+            _ => Err(PokerError::NotShowdownTurn),
+        }
+    }
 
-        todo!();
+    /// Submit whether the player next to the big blind straddles, putting twice the big blind
+    /// before the cards are dealt. The straddler acts last on the first betting round.
+    pub fn submit_straddle(
+        &mut self,
+        player_id: PlayerId,
+        straddle: bool,
+    ) -> Result<(), PokerError> {
+        match self.status {
+            PokerStatus::StraddleDecision { player_id: turn } if turn == player_id => {
+                if straddle {
+                    self.try_stake(player_id, 2 * self.blinds().big_blind)?;
+                    self.straddler = Some(player_id);
+                }
+                self.deal();
+                Ok(())
+            }
+            _ => Err(PokerError::NotStraddleTurn),
+        }
     }
 
     /// Submit the bet option from the player that is its turn.
@@ -346,86 +1055,108 @@ impl Poker {
         match self.status.clone() {
             PokerStatus::Betting {
                 target,
-                until,
-                raised,
                 max_stake,
+                min_raise,
+                bets,
                 next_stage,
             } => {
                 if target != action.player_id {
                     return Err(PokerError::NotBettingTurn);
                 }
 
-                match action.action {
+                let player_id = action.player_id as usize;
+                let staked = self.staked[player_id];
+                let total = self.stack(action.player_id);
+
+                let stake = match action.action {
                     BetAction::Fold => {
-                        self.folded[action.player_id as usize] = true;
-                        let next_player = self.next_on_game(action.player_id);
+                        self.folded[player_id] = true;
 
                         if self.total_folded() + 1 == self.num_players() {
                             // All players but one have folded. That is the winner.
-                            self.finish(vec![next_player]);
-                            Ok(())
+                            self.finish(vec![None; self.num_players() as usize]);
                         } else {
-                            self.status = PokerStatus::Betting {
-                                target: next_player,
-                                until,
-                                raised,
-                                max_stake,
-                                next_stage,
-                            };
-                            Ok(())
+                            self.continue_betting(action.player_id, next_stage, min_raise, bets);
                         }
+                        return Ok(());
                     }
-                    BetAction::Stake(stake) => {
-                        if stake < max_stake {
-                            Err(PokerError::TooLowStake)
-                        } else if stake > self.tokens[action.player_id as usize] {
-                            Err(PokerError::NotEnoughStake)
-                        } else {
-                            self.staked[action.player_id as usize] = stake;
-
-                            if stake > max_stake {
-                                // Raise
-                                // TODO: Put a lower bound on raising
-                                let next_player =
-                                    self.next_on_game(self.next_player(action.player_id));
-
-                                self.status = PokerStatus::Betting {
-                                    target: next_player,
-                                    until: next_player,
-                                    raised: true,
-                                    max_stake: stake,
-                                    next_stage,
-                                };
-                                Ok(())
-                            } else {
-                                // Call
-                                if action.player_id == until {
-                                    // Finish betting round. All players call big blind bet.
-                                    self.start_stage(next_stage);
-                                    Ok(())
-                                } else {
-                                    let next_player =
-                                        self.next_on_game(self.next_player(action.player_id));
-                                    if next_player != until || !raised {
-                                        // Missing some players to place their bets.
-                                        self.status = PokerStatus::Betting {
-                                            target: next_player,
-                                            until,
-                                            raised,
-                                            max_stake,
-                                            next_stage,
-                                        };
-                                        Ok(())
-                                    } else {
-                                        // Finish betting round. All players call last raised stake.
-                                        self.start_stage(next_stage);
-                                        Ok(())
-                                    }
-                                }
-                            }
+                    BetAction::Check => {
+                        if staked < max_stake {
+                            return Err(PokerError::CannotCheck);
+                        }
+                        staked
+                    }
+                    BetAction::Call => {
+                        if staked >= max_stake {
+                            return Err(PokerError::NothingToCall);
                         }
+                        std::cmp::min(max_stake, total)
                     }
+                    BetAction::Bet(amount) => {
+                        if bets > 0 {
+                            return Err(PokerError::CannotBet);
+                        }
+                        if amount == 0 {
+                            return Err(PokerError::TooLowStake);
+                        }
+                        max_stake
+                            .checked_add(amount)
+                            .ok_or(PokerError::NotEnoughStake)?
+                    }
+                    BetAction::Raise(stake) => {
+                        if bets == 0 {
+                            return Err(PokerError::CannotRaise);
+                        }
+                        if stake <= max_stake {
+                            return Err(PokerError::TooLowStake);
+                        }
+                        stake
+                    }
+                    BetAction::AllIn => total,
+                };
+
+                if stake > total {
+                    return Err(PokerError::NotEnoughStake);
+                }
+
+                if stake <= max_stake {
+                    // Check or call.
+                    self.try_stake(action.player_id, stake)?;
+                    self.continue_betting(action.player_id, next_stage, min_raise, bets);
+                    return Ok(());
                 }
+
+                // Bet or raise.
+                let (min_stake, max_allowed) =
+                    self.raise_limits(action.player_id, max_stake, min_raise, bets, &next_stage)?;
+
+                if stake > max_allowed {
+                    return Err(PokerError::TooHighStake);
+                }
+
+                if stake < min_stake && stake < total {
+                    return Err(PokerError::TooLowStake);
+                }
+
+                self.try_stake(action.player_id, stake)?;
+                self.last_aggressor = Some(action.player_id);
+
+                if stake >= min_stake {
+                    // Full raise. All other players should act again.
+                    self.acted = vec![false; self.num_players() as usize];
+                    self.continue_betting(
+                        action.player_id,
+                        next_stage,
+                        stake - max_stake,
+                        bets + 1,
+                    );
+                } else {
+                    // All-in lower than a full raise doesn't reopen the betting to players
+                    // that already acted. They only need to call the new stake or fold.
+                    self.continue_betting(action.player_id, next_stage, min_raise, bets);
+                }
+
+                Ok(())
             }
             _ => Err(PokerError::NotBettingRound),
         }
@@ -435,3 +1166,735 @@ impl Poker {
         self.tokens.len() as u64
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{BlindLevel, LevelDuration};
+
+    /// Table with blinds 5/10 and no antes.
+    fn config(betting_structure: BettingStructure) -> TableConfig {
+        TableConfig {
+            blind_levels: vec![BlindLevel {
+                small_blind: 5,
+                big_blind: 10,
+                ante: 0,
+                duration: LevelDuration::Hands(100),
+            }],
+            betting_structure,
+            ..Default::default()
+        }
+    }
+
+    fn table(config: &TableConfig, tokens: &[u64]) -> Poker {
+        let mut poker = Poker::new(config);
+        for &tokens in tokens {
+            poker.new_player(tokens);
+        }
+        poker
+    }
+
+    /// Move the hand forward until some player should act or the cards are revealed at
+    /// showdown. `cards` holds the value of every card of the deck, by card id.
+    fn advance(poker: &mut Poker, cards: &[Option<u64>]) {
+        loop {
+            match poker.get_status() {
+                PokerStatus::StraddleDecision { .. }
+                | PokerStatus::Betting { .. }
+                | PokerStatus::ShowdownDecision { .. } => return,
+                PokerStatus::WaitingRevealedCards => {
+                    poker.submit_revealed_cards(cards.to_vec());
+                    return;
+                }
+                _ => poker.next(),
+            }
+        }
+    }
+
+    fn act(poker: &mut Poker, player_id: PlayerId, action: BetAction) -> Result<(), PokerError> {
+        poker.submit_bet_action(ActionResponse { player_id, action })
+    }
+
+    fn max_stake(poker: &Poker) -> u64 {
+        match poker.get_status() {
+            PokerStatus::Betting { max_stake, .. } => max_stake,
+            _ => panic!("Not a betting round."),
+        }
+    }
+
+    #[test]
+    fn straddle_is_optional() {
+        let config = TableConfig {
+            straddle: true,
+            ..config(BettingStructure::NoLimit)
+        };
+        let mut poker = table(&config, &[1000, 1000, 1000, 1000]);
+        advance(&mut poker, &[]);
+
+        // Button 0, small blind 1, big blind 2. Player 3 decides whether to straddle.
+        assert!(poker.get_status() == PokerStatus::StraddleDecision { player_id: 3 });
+        assert_eq!(poker.get_turn(), Some(3));
+        assert!(matches!(
+            poker.submit_straddle(0, true),
+            Err(PokerError::NotStraddleTurn)
+        ));
+
+        poker.submit_straddle(3, false).unwrap();
+        advance(&mut poker, &[]);
+        assert_eq!(poker.staked, vec![0, 5, 10, 0]);
+        assert_eq!(poker.get_turn(), Some(3));
+        assert_eq!(max_stake(&poker), 10);
+        assert!(matches!(
+            poker.submit_straddle(3, true),
+            Err(PokerError::NotStraddleTurn)
+        ));
+    }
+
+    #[test]
+    fn straddler_acts_last() {
+        let config = TableConfig {
+            straddle: true,
+            ..config(BettingStructure::NoLimit)
+        };
+        let mut poker = table(&config, &[1000, 1000, 1000, 1000]);
+        advance(&mut poker, &[]);
+        poker.submit_straddle(3, true).unwrap();
+        advance(&mut poker, &[]);
+
+        assert_eq!(poker.staked, vec![0, 5, 10, 20]);
+        assert_eq!(poker.get_turn(), Some(0));
+        let legal_actions = poker.legal_actions().unwrap();
+        assert_eq!(legal_actions.call, Some(20));
+        assert_eq!(legal_actions.min_raise, Some(40));
+
+        act(&mut poker, 0, BetAction::Call).unwrap();
+        act(&mut poker, 1, BetAction::Call).unwrap();
+        act(&mut poker, 2, BetAction::Call).unwrap();
+        assert_eq!(poker.get_turn(), Some(3));
+        act(&mut poker, 3, BetAction::Check).unwrap();
+        assert!(matches!(poker.get_status(), PokerStatus::Revealing { .. }));
+    }
+
+    #[test]
+    fn no_straddle_heads_up() {
+        let config = TableConfig {
+            straddle: true,
+            ..config(BettingStructure::NoLimit)
+        };
+        let mut poker = table(&config, &[1000, 1000]);
+        advance(&mut poker, &[]);
+        assert!(matches!(poker.get_status(), PokerStatus::Betting { .. }));
+    }
+
+    /// Every card of the deck, by card id.
+    fn deck() -> Vec<Option<u64>> {
+        (0..52).map(Some).collect()
+    }
+
+    #[test]
+    fn stakes_accumulate_across_streets() {
+        let mut poker = table(&config(BettingStructure::NoLimit), &[1000, 1000, 1000]);
+        advance(&mut poker, &[]);
+
+        // Button 0, small blind 1, big blind 2.
+        act(&mut poker, 0, BetAction::Call).unwrap();
+        act(&mut poker, 1, BetAction::Call).unwrap();
+        act(&mut poker, 2, BetAction::Check).unwrap();
+        advance(&mut poker, &[]);
+
+        // Flop betting starts left of the button. Stakes are totals for the whole hand.
+        assert_eq!(poker.get_turn(), Some(1));
+        assert_eq!(max_stake(&poker), 10);
+        act(&mut poker, 1, BetAction::Bet(20)).unwrap();
+        act(&mut poker, 2, BetAction::Call).unwrap();
+        act(&mut poker, 0, BetAction::Call).unwrap();
+        assert_eq!(poker.staked, vec![30, 30, 30]);
+        advance(&mut poker, &[]);
+
+        assert_eq!(max_stake(&poker), 30);
+        act(&mut poker, 1, BetAction::Check).unwrap();
+        act(&mut poker, 2, BetAction::Bet(50)).unwrap();
+        assert_eq!(max_stake(&poker), 80);
+        assert_eq!(poker.legal_actions().unwrap().call, Some(50));
+        act(&mut poker, 0, BetAction::Fold).unwrap();
+        act(&mut poker, 1, BetAction::Fold).unwrap();
+
+        // Folded stakes are lost.
+        assert!(poker.get_status() == PokerStatus::Idle);
+        assert_eq!(poker.tokens, vec![970, 970, 1060]);
+    }
+
+    #[test]
+    fn all_in_players_are_skipped() {
+        let mut poker = table(&config(BettingStructure::NoLimit), &[1000, 50, 1000]);
+        advance(&mut poker, &[]);
+
+        act(&mut poker, 0, BetAction::Call).unwrap();
+        act(&mut poker, 1, BetAction::AllIn).unwrap();
+        assert!(poker.all_in[1]);
+        act(&mut poker, 2, BetAction::Call).unwrap();
+        act(&mut poker, 0, BetAction::Call).unwrap();
+        advance(&mut poker, &[]);
+
+        // Player 1, left of the button, is all-in and doesn't act anymore.
+        assert_eq!(poker.get_turn(), Some(2));
+        act(&mut poker, 2, BetAction::Bet(100)).unwrap();
+        act(&mut poker, 0, BetAction::Call).unwrap();
+        assert_eq!(poker.staked, vec![150, 50, 150]);
+        advance(&mut poker, &[]);
+        assert_eq!(poker.get_turn(), Some(2));
+        assert!(matches!(
+            act(&mut poker, 1, BetAction::Check),
+            Err(PokerError::NotBettingTurn)
+        ));
+    }
+
+    #[test]
+    fn board_runs_out_when_at_most_one_player_can_bet() {
+        let mut poker = table(&config(BettingStructure::NoLimit), &[1000, 50, 100]);
+        advance(&mut poker, &[]);
+
+        act(&mut poker, 0, BetAction::Call).unwrap();
+        act(&mut poker, 1, BetAction::AllIn).unwrap();
+        act(&mut poker, 2, BetAction::AllIn).unwrap();
+        act(&mut poker, 0, BetAction::Call).unwrap();
+
+        // Player 0 is the only one that can bet, so the board is revealed up to showdown.
+        let cards = deck();
+        advance(&mut poker, &cards);
+        assert_eq!(poker.first_unrevealed_card, 6 + 5);
+        assert!(poker.get_status() == PokerStatus::ShowdownDecision { player_id: 2 });
+        poker
+            .submit_showdown_action(2, ShowdownAction::Show)
+            .unwrap();
+        advance(&mut poker, &cards);
+        poker
+            .submit_showdown_action(0, ShowdownAction::Show)
+            .unwrap();
+        advance(&mut poker, &cards);
+
+        assert!(poker.get_status() == PokerStatus::Idle);
+        assert_eq!(poker.tokens.iter().sum::<u64>(), 1150);
+    }
+
+    /// Table on the first betting round, where player 0 should act.
+    fn preflop_with(tokens: &[u64], betting_structure: BettingStructure) -> Poker {
+        let mut poker = table(&config(betting_structure), tokens);
+        advance(&mut poker, &[]);
+        poker
+    }
+
+    /// Table with three players on the first betting round, where player 0 should act.
+    fn preflop() -> Poker {
+        preflop_with(&[1000, 1000, 1000], BettingStructure::NoLimit)
+    }
+
+    /// Table with three players on the flop, where nobody bet yet and player 1 should act.
+    fn flop() -> Poker {
+        let mut poker = preflop();
+        act(&mut poker, 0, BetAction::Call).unwrap();
+        act(&mut poker, 1, BetAction::Call).unwrap();
+        act(&mut poker, 2, BetAction::Check).unwrap();
+        advance(&mut poker, &[]);
+        poker
+    }
+
+    #[test]
+    fn cannot_check_facing_a_bet() {
+        let mut poker = preflop();
+        assert!(matches!(
+            act(&mut poker, 0, BetAction::Check),
+            Err(PokerError::CannotCheck)
+        ));
+    }
+
+    #[test]
+    fn nothing_to_call_when_stake_is_matched() {
+        let mut poker = preflop();
+        act(&mut poker, 0, BetAction::Call).unwrap();
+        act(&mut poker, 1, BetAction::Call).unwrap();
+        assert!(matches!(
+            act(&mut poker, 2, BetAction::Call),
+            Err(PokerError::NothingToCall)
+        ));
+    }
+
+    #[test]
+    fn cannot_bet_after_a_bet() {
+        // Blinds count as the opening bet.
+        let mut poker = preflop();
+        assert!(matches!(
+            act(&mut poker, 0, BetAction::Bet(20)),
+            Err(PokerError::CannotBet)
+        ));
+    }
+
+    #[test]
+    fn cannot_raise_without_a_bet() {
+        let mut poker = flop();
+        assert!(matches!(
+            act(&mut poker, 1, BetAction::Raise(30)),
+            Err(PokerError::CannotRaise)
+        ));
+    }
+
+    #[test]
+    fn too_low_stake() {
+        let mut poker = preflop();
+        for &stake in [10, 15].iter() {
+            assert!(matches!(
+                act(&mut poker, 0, BetAction::Raise(stake)),
+                Err(PokerError::TooLowStake)
+            ));
+        }
+
+        let mut poker = flop();
+        assert!(matches!(
+            act(&mut poker, 1, BetAction::Bet(0)),
+            Err(PokerError::TooLowStake)
+        ));
+        assert!(matches!(
+            act(&mut poker, 1, BetAction::Bet(5)),
+            Err(PokerError::TooLowStake)
+        ));
+    }
+
+    #[test]
+    fn not_enough_stake() {
+        let mut poker = preflop();
+        assert!(matches!(
+            act(&mut poker, 0, BetAction::Raise(1001)),
+            Err(PokerError::NotEnoughStake)
+        ));
+
+        let mut poker = flop();
+        assert!(matches!(
+            act(&mut poker, 1, BetAction::Bet(991)),
+            Err(PokerError::NotEnoughStake)
+        ));
+        assert!(matches!(
+            act(&mut poker, 1, BetAction::Bet(u64::MAX)),
+            Err(PokerError::NotEnoughStake)
+        ));
+
+        // The stake is unchanged after a rejected action.
+        assert_eq!(poker.staked, vec![10, 10, 10]);
+        assert_eq!(poker.get_turn(), Some(1));
+    }
+
+    #[test]
+    fn short_all_in_does_not_reopen_betting() {
+        let mut poker = table(&config(BettingStructure::NoLimit), &[1000, 1000, 45]);
+        advance(&mut poker, &[]);
+        act(&mut poker, 0, BetAction::Call).unwrap();
+        act(&mut poker, 1, BetAction::Call).unwrap();
+        act(&mut poker, 2, BetAction::Check).unwrap();
+        advance(&mut poker, &[]);
+
+        // Player 2 raises 15 going all-in, less than the bet of 20.
+        act(&mut poker, 1, BetAction::Bet(20)).unwrap();
+        act(&mut poker, 2, BetAction::AllIn).unwrap();
+        assert_eq!(max_stake(&poker), 45);
+
+        // Player 0 didn't act yet, so it can raise a full raise over the all-in.
+        assert_eq!(poker.legal_actions().unwrap().min_raise, Some(65));
+        act(&mut poker, 0, BetAction::Call).unwrap();
+
+        // Player 1 already acted, so it can only call or fold.
+        let legal_actions = poker.legal_actions().unwrap();
+        assert_eq!(legal_actions.call, Some(15));
+        assert_eq!(legal_actions.min_raise, None);
+        assert_eq!(legal_actions.all_in, None);
+        assert!(matches!(
+            act(&mut poker, 1, BetAction::Raise(100)),
+            Err(PokerError::CannotReraise)
+        ));
+        assert!(matches!(
+            act(&mut poker, 1, BetAction::AllIn),
+            Err(PokerError::CannotReraise)
+        ));
+        act(&mut poker, 1, BetAction::Call).unwrap();
+        assert!(matches!(poker.get_status(), PokerStatus::Revealing { .. }));
+    }
+
+    #[test]
+    fn full_raise_reopens_betting() {
+        let mut poker = flop();
+        act(&mut poker, 1, BetAction::Bet(20)).unwrap();
+        act(&mut poker, 2, BetAction::Raise(50)).unwrap();
+        act(&mut poker, 0, BetAction::Call).unwrap();
+
+        // The raise was 20, so raising again requires at least 70.
+        assert_eq!(poker.get_turn(), Some(1));
+        assert_eq!(poker.legal_actions().unwrap().min_raise, Some(70));
+        assert!(matches!(
+            act(&mut poker, 1, BetAction::Raise(69)),
+            Err(PokerError::TooLowStake)
+        ));
+        act(&mut poker, 1, BetAction::Raise(70)).unwrap();
+        assert_eq!(poker.get_turn(), Some(2));
+    }
+
+    #[test]
+    fn fixed_limit_caps_raises() {
+        let mut poker = table(&config(BettingStructure::FixedLimit), &[1000, 1000, 1000]);
+        advance(&mut poker, &[]);
+
+        // The big blind is the first bet, and three raises reach the cap.
+        act(&mut poker, 0, BetAction::Raise(20)).unwrap();
+        act(&mut poker, 1, BetAction::Raise(30)).unwrap();
+        act(&mut poker, 2, BetAction::Raise(40)).unwrap();
+
+        let legal_actions = poker.legal_actions().unwrap();
+        assert_eq!(legal_actions.call, Some(20));
+        assert_eq!(legal_actions.min_raise, None);
+        assert!(matches!(
+            act(&mut poker, 0, BetAction::Raise(50)),
+            Err(PokerError::BettingCapReached)
+        ));
+        act(&mut poker, 0, BetAction::Call).unwrap();
+        act(&mut poker, 1, BetAction::Call).unwrap();
+        assert!(matches!(poker.get_status(), PokerStatus::Revealing { .. }));
+    }
+
+    #[test]
+    fn legal_actions_amounts() {
+        let mut poker = preflop();
+        assert_eq!(
+            poker.legal_actions(),
+            Some(LegalActions {
+                player_id: 0,
+                can_fold: true,
+                can_check: false,
+                call: Some(10),
+                is_bet: false,
+                min_raise: Some(20),
+                max_raise: Some(1000),
+                all_in: Some(1000),
+            })
+        );
+
+        act(&mut poker, 0, BetAction::Call).unwrap();
+        act(&mut poker, 1, BetAction::Call).unwrap();
+        let legal_actions = poker.legal_actions().unwrap();
+        assert!(legal_actions.can_check);
+        assert_eq!(legal_actions.call, None);
+
+        let poker = flop();
+        let legal_actions = poker.legal_actions().unwrap();
+        assert_eq!(legal_actions.player_id, 1);
+        assert!(legal_actions.is_bet);
+        assert_eq!(legal_actions.min_raise, Some(20));
+        assert_eq!(legal_actions.max_raise, Some(1000));
+    }
+
+    #[test]
+    fn legal_actions_of_short_stacks() {
+        // Not enough for a full raise, but enough to call.
+        let legal_actions = preflop_with(&[15, 1000, 1000], BettingStructure::NoLimit)
+            .legal_actions()
+            .unwrap();
+        assert_eq!(legal_actions.call, Some(10));
+        assert_eq!(legal_actions.min_raise, None);
+        assert_eq!(legal_actions.max_raise, None);
+        assert_eq!(legal_actions.all_in, Some(15));
+
+        // Not enough to call, calling is going all-in.
+        let legal_actions = preflop_with(&[5, 1000, 1000], BettingStructure::NoLimit)
+            .legal_actions()
+            .unwrap();
+        assert_eq!(legal_actions.call, Some(5));
+        assert_eq!(legal_actions.min_raise, None);
+        assert_eq!(legal_actions.all_in, Some(5));
+    }
+
+    #[test]
+    fn pot_limit_raises_up_to_call_and_pot() {
+        let mut poker = preflop_with(&[1000, 1000, 1000], BettingStructure::PotLimit);
+
+        // Call 10, then raise the pot of 25 after calling.
+        let legal_actions = poker.legal_actions().unwrap();
+        assert_eq!(legal_actions.min_raise, Some(20));
+        assert_eq!(legal_actions.max_raise, Some(35));
+        assert_eq!(legal_actions.all_in, None);
+        assert!(matches!(
+            act(&mut poker, 0, BetAction::Raise(36)),
+            Err(PokerError::TooHighStake)
+        ));
+        assert!(matches!(
+            act(&mut poker, 0, BetAction::AllIn),
+            Err(PokerError::TooHighStake)
+        ));
+        act(&mut poker, 0, BetAction::Raise(35)).unwrap();
+
+        // Call 30, then raise the pot of 80 after calling.
+        assert_eq!(poker.legal_actions().unwrap().max_raise, Some(115));
+        act(&mut poker, 1, BetAction::Raise(115)).unwrap();
+
+        // Short stacks can go all-in when it is below the limit.
+        let poker = preflop_with(&[30, 1000, 1000], BettingStructure::PotLimit);
+        let legal_actions = poker.legal_actions().unwrap();
+        assert_eq!(legal_actions.max_raise, Some(30));
+        assert_eq!(legal_actions.all_in, Some(30));
+    }
+
+    #[test]
+    fn fixed_limit_sizes_per_street() {
+        let mut poker = preflop_with(&[1000, 1000, 1000], BettingStructure::FixedLimit);
+        let legal_actions = poker.legal_actions().unwrap();
+        assert_eq!(legal_actions.min_raise, Some(20));
+        assert_eq!(legal_actions.max_raise, Some(20));
+        assert!(matches!(
+            act(&mut poker, 0, BetAction::Raise(30)),
+            Err(PokerError::TooHighStake)
+        ));
+        act(&mut poker, 0, BetAction::Call).unwrap();
+        act(&mut poker, 1, BetAction::Call).unwrap();
+        act(&mut poker, 2, BetAction::Check).unwrap();
+
+        // Small bets on the flop, big bets on the turn and the river.
+        for &size in [10, 20, 20].iter() {
+            advance(&mut poker, &[]);
+            let legal_actions = poker.legal_actions().unwrap();
+            assert_eq!(legal_actions.min_raise, Some(10 + size));
+            assert_eq!(legal_actions.max_raise, Some(10 + size));
+            for player_id in [1, 2, 0].iter() {
+                act(&mut poker, *player_id, BetAction::Check).unwrap();
+            }
+        }
+    }
+
+    /// Deck where, with three players and the button on player 0, player 0 holds aces,
+    /// player 1 kings and player 2 queens. The board doesn't help any of them.
+    fn showdown_deck() -> Vec<Option<u64>> {
+        // Player 1 gets cards 0 and 1, player 2 cards 2 and 3, player 0 cards 4 and 5.
+        let mut cards = vec![11, 24, 10, 23, 12, 25, 0, 18, 33, 48, 2];
+        cards.resize(52, 51);
+        cards.into_iter().map(Some).collect()
+    }
+
+    /// Play the flop and the turn checking, up to the river betting round.
+    fn river() -> Poker {
+        let mut poker = flop();
+        for _ in 0..2 {
+            for player_id in [1, 2, 0].iter() {
+                act(&mut poker, *player_id, BetAction::Check).unwrap();
+            }
+            advance(&mut poker, &[]);
+        }
+        poker
+    }
+
+    #[test]
+    fn last_aggressor_shows_first() {
+        let cards = showdown_deck();
+        let mut poker = river();
+        act(&mut poker, 1, BetAction::Check).unwrap();
+        act(&mut poker, 2, BetAction::Bet(20)).unwrap();
+        act(&mut poker, 0, BetAction::Call).unwrap();
+        act(&mut poker, 1, BetAction::Call).unwrap();
+
+        // Player 2 can't muck, its hole cards are revealed.
+        assert!(
+            poker.get_status()
+                == PokerStatus::Showdown {
+                    player_id: 2,
+                    card_id: 2,
+                    first_card: true,
+                }
+        );
+        advance(&mut poker, &cards);
+
+        // Then clockwise. Mucking gives up the pot, even with the best hand.
+        assert!(poker.get_status() == PokerStatus::ShowdownDecision { player_id: 0 });
+        poker
+            .submit_showdown_action(0, ShowdownAction::Muck)
+            .unwrap();
+        assert!(poker.get_status() == PokerStatus::ShowdownDecision { player_id: 1 });
+        assert!(matches!(
+            poker.submit_showdown_action(2, ShowdownAction::Show),
+            Err(PokerError::NotShowdownTurn)
+        ));
+        poker
+            .submit_showdown_action(1, ShowdownAction::Show)
+            .unwrap();
+        advance(&mut poker, &cards);
+
+        assert_eq!(
+            poker.showdown,
+            vec![
+                (2, ShowdownAction::Show),
+                (0, ShowdownAction::Muck),
+                (1, ShowdownAction::Show),
+            ]
+        );
+        assert!(poker.get_status() == PokerStatus::Idle);
+        assert_eq!(poker.tokens, vec![970, 1060, 970]);
+    }
+
+    #[test]
+    fn first_player_left_of_button_shows_first_without_aggressor() {
+        let cards = showdown_deck();
+        let mut poker = river();
+        for player_id in [1, 2, 0].iter() {
+            act(&mut poker, *player_id, BetAction::Check).unwrap();
+        }
+        advance(&mut poker, &cards);
+        assert!(poker.get_status() == PokerStatus::ShowdownDecision { player_id: 2 });
+
+        for player_id in [2, 0].iter() {
+            poker
+                .submit_showdown_action(*player_id, ShowdownAction::Show)
+                .unwrap();
+            advance(&mut poker, &cards);
+        }
+
+        let order: Vec<_> = poker
+            .showdown
+            .iter()
+            .map(|(player_id, _)| *player_id)
+            .collect();
+        assert_eq!(order, vec![1, 2, 0]);
+        assert_eq!(poker.tokens, vec![1020, 990, 990]);
+    }
+
+    #[test]
+    fn heads_up_button_posts_small_blind() {
+        let mut poker = preflop_with(&[1000, 1000], BettingStructure::NoLimit);
+
+        // The button posts the small blind and acts first before the flop.
+        assert_eq!(poker.button, Some(0));
+        assert_eq!(poker.staked, vec![5, 10]);
+        assert_eq!(poker.get_turn(), Some(0));
+        act(&mut poker, 0, BetAction::Call).unwrap();
+        act(&mut poker, 1, BetAction::Check).unwrap();
+
+        // And last after the flop.
+        advance(&mut poker, &[]);
+        assert_eq!(poker.get_turn(), Some(1));
+        act(&mut poker, 1, BetAction::Bet(10)).unwrap();
+        act(&mut poker, 0, BetAction::Fold).unwrap();
+
+        advance(&mut poker, &[]);
+        assert_eq!(poker.button, Some(1));
+        assert_eq!(poker.staked, vec![10, 5]);
+        assert_eq!(poker.get_turn(), Some(1));
+    }
+
+    /// Play a hand with four players where everybody folds to the big blind, player 2.
+    fn fold_to_big_blind() -> Poker {
+        let mut poker = preflop_with(&[1000, 1000, 1000, 1000], BettingStructure::NoLimit);
+        for player_id in [3, 0, 1].iter() {
+            act(&mut poker, *player_id, BetAction::Fold).unwrap();
+        }
+        assert!(poker.get_status() == PokerStatus::Idle);
+        poker
+    }
+
+    #[test]
+    fn button_skips_eliminated_small_blind() {
+        let mut poker = fold_to_big_blind();
+
+        // Small blind is eliminated. The blinds move to the next players, and the button
+        // stays before them.
+        poker.remove_player(1);
+        advance(&mut poker, &[]);
+        assert_eq!(poker.button, Some(0));
+        assert_eq!(poker.staked, vec![0, 5, 10]);
+        assert_eq!(poker.get_turn(), Some(0));
+        assert!(
+            poker.get_status()
+                == PokerStatus::Betting {
+                    target: 0,
+                    max_stake: 10,
+                    min_raise: 10,
+                    bets: 1,
+                    next_stage: Stage::Flop,
+                }
+        );
+    }
+
+    #[test]
+    fn dead_small_blind_after_big_blind_is_eliminated() {
+        let mut poker = fold_to_big_blind();
+
+        // Big blind is eliminated. Nobody posts the small blind on the next hand, and the
+        // next player posts the big blind.
+        poker.remove_player(2);
+        poker.next();
+        assert_eq!(poker.button, Some(1));
+        assert_eq!(poker.staked, vec![0, 0, 10]);
+        advance(&mut poker, &[]);
+        assert_eq!(poker.get_turn(), Some(0));
+
+        // On the hand after it the blinds move normally. The button would be on the seat
+        // of the eliminated player, which is represented by the seat before it.
+        for player_id in [0, 1].iter() {
+            act(&mut poker, *player_id, BetAction::Fold).unwrap();
+        }
+        advance(&mut poker, &[]);
+        assert_eq!(poker.button, Some(1));
+        assert_eq!(poker.staked, vec![10, 0, 5]);
+        assert_eq!(poker.get_turn(), Some(1));
+    }
+
+    #[test]
+    fn busted_players_are_removed() {
+        let mut poker = preflop_with(&[100, 300, 100], BettingStructure::NoLimit);
+        act(&mut poker, 0, BetAction::AllIn).unwrap();
+        act(&mut poker, 1, BetAction::Call).unwrap();
+        act(&mut poker, 2, BetAction::Call).unwrap();
+
+        // Player 0 holds aces, player 1 kings and player 2 queens.
+        let cards = showdown_deck();
+        advance(&mut poker, &cards);
+        for player_id in [2, 0].iter() {
+            poker
+                .submit_showdown_action(*player_id, ShowdownAction::Show)
+                .unwrap();
+            advance(&mut poker, &cards);
+        }
+        assert_eq!(poker.tokens, vec![300, 200, 0]);
+        assert_eq!(poker.busted_players(), vec![2]);
+
+        poker.remove_player(2);
+        assert_eq!(poker.num_players(), 2);
+        assert_eq!(poker.busted_players(), Vec::<PlayerId>::new());
+        advance(&mut poker, &[]);
+        assert_eq!(poker.staked, vec![10, 5]);
+    }
+
+    #[test]
+    fn seats_move_down_after_removal() {
+        let config = TableConfig {
+            time_bank: Some(TimeBank {
+                initial: 100,
+                top_up: 10,
+                every_hands: 1,
+            }),
+            ..config(BettingStructure::NoLimit)
+        };
+        let mut poker = table(&config, &[100, 200, 300, 400]);
+        poker.use_time_bank(2, 30);
+
+        poker.remove_player(1);
+        assert_eq!(poker.tokens, vec![100, 300, 400]);
+        assert_eq!(poker.time_bank, vec![100, 70, 100]);
+        for len in [
+            poker.staked.len(),
+            poker.antes.len(),
+            poker.folded.len(),
+            poker.all_in.len(),
+            poker.acted.len(),
+        ]
+        .iter()
+        {
+            assert_eq!(*len, 3);
+        }
+
+        // The first hand is played with the remaining players.
+        advance(&mut poker, &[]);
+        assert_eq!(poker.staked, vec![0, 5, 10]);
+        assert_eq!(poker.get_turn(), Some(0));
+    }
+}
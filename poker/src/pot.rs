@@ -0,0 +1,370 @@
+use crate::hand::HandValue;
+use crate::types::PlayerId;
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::Serialize;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct Pot {
+    /// Total tokens in this pot.
+    pub amount: u64,
+    /// Players that have not folded and contributed enough to win this pot.
+    pub eligible: Vec<PlayerId>,
+    /// Players with the best hand among eligible players. The pot is split between them.
+    pub winners: Vec<PlayerId>,
+}
+
+/// Result of settling all pots at the end of a round.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct Settlement {
+    /// Main pot first, followed by every side pot.
+    pub pots: Vec<Pot>,
+    /// Tokens each player put in the pots, including antes.
+    pub contributed: Vec<u64>,
+    /// Tokens each player won from the pots.
+    pub payouts: Vec<u64>,
+}
+
+/// Split the stakes in main and side pots and award each of them to the best eligible hand.
+///
+/// Antes are paid before any stake, so they form the lowest levels of the pots. A player that
+/// couldn't stake anything after its ante only contests the antes it matched. Every other
+/// player contests every ante, so a big blind ante is dead money in the main pot.
+///
+/// `hands` holds the value of the hand of each player. Players whose hand is unknown
+/// (for example because all other players folded) are considered tied among them.
+/// When a pot can't be split evenly, the remaining tokens are given one by one to the
+/// winners in the order they appear in `seat_order`.
+pub fn settle(
+    staked: &[u64],
+    antes: &[u64],
+    folded: &[bool],
+    hands: &[Option<HandValue>],
+    seat_order: &[PlayerId],
+) -> Settlement {
+    let num_players = staked.len();
+
+    // Stakes are measured after the largest ante. Players without stake are all in on the ante.
+    let full_ante = antes.iter().cloned().max().unwrap_or(0);
+    let reached = |player_id: usize| {
+        if staked[player_id] == 0 {
+            antes[player_id]
+        } else {
+            full_ante + staked[player_id]
+        }
+    };
+    // Tokens put by a player below `level`.
+    let put_below = |player_id: usize, level: u64| {
+        antes[player_id].min(level) + staked[player_id].min(level.saturating_sub(full_ante))
+    };
+
+    // Every different level reached by players still in the game closes one pot.
+    let mut levels: Vec<u64> = (0..num_players)
+        .filter(|&player_id| !folded[player_id])
+        .map(reached)
+        .collect();
+    levels.sort();
+    levels.dedup();
+
+    let mut pots = vec![];
+    let mut previous_level = 0;
+
+    for level in levels {
+        let amount = (0..num_players)
+            .map(|player_id| put_below(player_id, level) - put_below(player_id, previous_level))
+            .sum();
+
+        let eligible: Vec<PlayerId> = (0..num_players)
+            .filter(|&player_id| !folded[player_id] && reached(player_id) >= level)
+            .map(|player_id| player_id as PlayerId)
+            .collect();
+
+        pots.push(Pot {
+            amount,
+            winners: best_hands(&eligible, hands),
+            eligible,
+        });
+
+        previous_level = level;
+    }
+
+    // Tokens staked by folded players above every other stake go to the last pot.
+    let remaining: u64 = (0..num_players)
+        .map(|player_id| {
+            antes[player_id] + staked[player_id] - put_below(player_id, previous_level)
+        })
+        .sum();
+
+    if let Some(last_pot) = pots.last_mut() {
+        last_pot.amount += remaining;
+    }
+
+    pots.retain(|pot| pot.amount > 0);
+
+    let mut payouts = vec![0; num_players];
+
+    for pot in pots.iter() {
+        let num_winners = pot.winners.len() as u64;
+        if num_winners == 0 {
+            continue;
+        }
+
+        for winner in pot.winners.iter() {
+            payouts[*winner as usize] += pot.amount / num_winners;
+        }
+
+        let odd_chips = (pot.amount % num_winners) as usize;
+        for winner in seat_order
+            .iter()
+            .filter(|player_id| pot.winners.contains(player_id))
+            .take(odd_chips)
+        {
+            payouts[*winner as usize] += 1;
+        }
+    }
+
+    Settlement {
+        pots,
+        contributed: staked
+            .iter()
+            .zip(antes.iter())
+            .map(|(stake, ante)| stake + ante)
+            .collect(),
+        payouts,
+    }
+}
+
+/// Players from `eligible` with the highest hand.
+fn best_hands(eligible: &[PlayerId], hands: &[Option<HandValue>]) -> Vec<PlayerId> {
+    let best = eligible
+        .iter()
+        .map(|&player_id| &hands[player_id as usize])
+        .max();
+
+    eligible
+        .iter()
+        .filter(|&&player_id| Some(&hands[player_id as usize]) == best)
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hand::HandCategory;
+
+    fn high_card(rank: u8) -> Option<HandValue> {
+        Some(HandValue {
+            category: HandCategory::HighCard,
+            ranks: vec![rank],
+        })
+    }
+
+    struct Case {
+        name: &'static str,
+        staked: Vec<u64>,
+        folded: Vec<bool>,
+        /// Rank of the high card of each player, None if unknown.
+        hands: Vec<Option<u8>>,
+        seat_order: Vec<PlayerId>,
+        /// Amount, eligible players and winners of each pot.
+        pots: Vec<(u64, Vec<PlayerId>, Vec<PlayerId>)>,
+        payouts: Vec<u64>,
+    }
+
+    #[test]
+    fn settle_cases() {
+        let cases = vec![
+            Case {
+                name: "single pot",
+                staked: vec![100, 100, 100],
+                folded: vec![false, false, false],
+                hands: vec![Some(3), Some(9), Some(5)],
+                seat_order: vec![1, 2, 0],
+                pots: vec![(300, vec![0, 1, 2], vec![1])],
+                payouts: vec![0, 300, 0],
+            },
+            Case {
+                name: "multi-level side pots",
+                staked: vec![50, 100, 200, 200],
+                folded: vec![false, false, false, false],
+                hands: vec![Some(12), Some(11), Some(10), Some(9)],
+                seat_order: vec![1, 2, 3, 0],
+                pots: vec![
+                    (200, vec![0, 1, 2, 3], vec![0]),
+                    (150, vec![1, 2, 3], vec![1]),
+                    (200, vec![2, 3], vec![2]),
+                ],
+                payouts: vec![200, 150, 200, 0],
+            },
+            Case {
+                name: "short stack loses every pot",
+                staked: vec![50, 100, 200, 200],
+                folded: vec![false, false, false, false],
+                hands: vec![Some(2), Some(11), Some(10), Some(12)],
+                seat_order: vec![1, 2, 3, 0],
+                pots: vec![
+                    (200, vec![0, 1, 2, 3], vec![3]),
+                    (150, vec![1, 2, 3], vec![3]),
+                    (200, vec![2, 3], vec![3]),
+                ],
+                payouts: vec![0, 0, 0, 550],
+            },
+            Case {
+                name: "folded contributors don't open pots",
+                staked: vec![100, 30, 100, 60],
+                folded: vec![false, true, false, true],
+                hands: vec![Some(5), Some(12), Some(7), None],
+                seat_order: vec![1, 2, 3, 0],
+                pots: vec![(290, vec![0, 2], vec![2])],
+                payouts: vec![0, 0, 290, 0],
+            },
+            Case {
+                name: "folded stake above every other stake",
+                staked: vec![40, 100, 40],
+                folded: vec![false, true, false],
+                hands: vec![Some(8), None, Some(4)],
+                seat_order: vec![1, 2, 0],
+                pots: vec![(180, vec![0, 2], vec![0])],
+                payouts: vec![180, 0, 0],
+            },
+            Case {
+                name: "everybody else folded",
+                staked: vec![10, 20, 5],
+                folded: vec![true, false, true],
+                hands: vec![None, None, None],
+                seat_order: vec![1, 2, 0],
+                pots: vec![(35, vec![1], vec![1])],
+                payouts: vec![0, 35, 0],
+            },
+            Case {
+                name: "split pot",
+                staked: vec![100, 100, 100],
+                folded: vec![false, false, false],
+                hands: vec![Some(12), Some(12), Some(5)],
+                seat_order: vec![1, 2, 0],
+                pots: vec![(300, vec![0, 1, 2], vec![0, 1])],
+                payouts: vec![150, 150, 0],
+            },
+            Case {
+                name: "split main pot, side pot to one player",
+                staked: vec![50, 100, 100],
+                folded: vec![false, false, false],
+                hands: vec![Some(12), Some(12), Some(5)],
+                seat_order: vec![1, 2, 0],
+                pots: vec![(150, vec![0, 1, 2], vec![0, 1]), (100, vec![1, 2], vec![1])],
+                payouts: vec![75, 175, 0],
+            },
+            Case {
+                name: "odd chip to first winner in seat order",
+                staked: vec![25, 25, 25],
+                folded: vec![false, false, true],
+                hands: vec![Some(9), Some(9), None],
+                seat_order: vec![1, 2, 0],
+                pots: vec![(75, vec![0, 1], vec![0, 1])],
+                payouts: vec![37, 38, 0],
+            },
+            Case {
+                name: "odd chip follows seat order, not player id",
+                staked: vec![25, 25, 25],
+                folded: vec![false, false, true],
+                hands: vec![Some(9), Some(9), None],
+                seat_order: vec![2, 0, 1],
+                pots: vec![(75, vec![0, 1], vec![0, 1])],
+                payouts: vec![38, 37, 0],
+            },
+            Case {
+                name: "odd chips one by one",
+                staked: vec![11, 11, 11, 11],
+                folded: vec![false, false, false, true],
+                hands: vec![Some(4), Some(4), Some(4), None],
+                seat_order: vec![2, 3, 0, 1],
+                pots: vec![(44, vec![0, 1, 2], vec![0, 1, 2])],
+                payouts: vec![15, 14, 15, 0],
+            },
+        ];
+
+        for case in cases {
+            let hands = case
+                .hands
+                .iter()
+                .map(|rank| rank.and_then(high_card))
+                .collect::<Vec<_>>();
+            let antes = vec![0; case.staked.len()];
+            let settlement = settle(&case.staked, &antes, &case.folded, &hands, &case.seat_order);
+
+            let pots = case
+                .pots
+                .into_iter()
+                .map(|(amount, eligible, winners)| Pot {
+                    amount,
+                    eligible,
+                    winners,
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(settlement.pots, pots, "{}", case.name);
+            assert_eq!(settlement.payouts, case.payouts, "{}", case.name);
+            assert_eq!(settlement.contributed, case.staked, "{}", case.name);
+            assert_eq!(
+                settlement.payouts.iter().sum::<u64>(),
+                settlement.contributed.iter().sum::<u64>(),
+                "{}",
+                case.name
+            );
+        }
+    }
+
+    #[test]
+    fn big_blind_ante_is_dead_money() {
+        // Blinds 5/10 with a big blind ante of 10. Everyone limps, so the big blind
+        // contributes 20 while the others contribute 10.
+        let settlement = settle(
+            &[10, 10, 10],
+            &[0, 0, 10],
+            &[false, false, false],
+            &[high_card(12), high_card(11), high_card(10)],
+            &[1, 2, 0],
+        );
+
+        assert_eq!(
+            settlement.pots,
+            vec![Pot {
+                amount: 40,
+                eligible: vec![0, 1, 2],
+                winners: vec![0],
+            }]
+        );
+        assert_eq!(settlement.contributed, vec![10, 10, 20]);
+        assert_eq!(settlement.payouts, vec![40, 0, 0]);
+    }
+
+    #[test]
+    fn all_in_on_ante_only_wins_antes() {
+        // Player 2 can only pay half of the ante and stakes nothing, so it only contests
+        // the first half of every ante.
+        let settlement = settle(
+            &[20, 20, 0],
+            &[10, 10, 5],
+            &[false, false, false],
+            &[high_card(10), high_card(11), high_card(12)],
+            &[1, 2, 0],
+        );
+
+        assert_eq!(
+            settlement.pots,
+            vec![
+                Pot {
+                    amount: 15,
+                    eligible: vec![0, 1, 2],
+                    winners: vec![2],
+                },
+                Pot {
+                    amount: 50,
+                    eligible: vec![0, 1],
+                    winners: vec![1],
+                },
+            ]
+        );
+        assert_eq!(settlement.contributed, vec![30, 30, 5]);
+        assert_eq!(settlement.payouts, vec![0, 50, 15]);
+    }
+}
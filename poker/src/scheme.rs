@@ -0,0 +1,128 @@
+use crate::bigint::U256;
+use crate::elgamal;
+use crate::sra;
+use crate::types::{GroupElement, PlayerId};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+/// Cryptographic scheme used by a deck to encrypt, shuffle and reveal cards.
+pub trait Scheme {
+    /// Key registered by each player when entering the room.
+    type PublicKey: Clone + BorshDeserialize + BorshSerialize + Serialize;
+    /// Encrypted card.
+    type Card: Clone + PartialEq + BorshDeserialize + BorshSerialize + Serialize;
+    type ShuffleProof;
+    /// Value submitted by a player to remove its encryption from a card.
+    type RevealPart: Clone + BorshDeserialize + BorshSerialize + Serialize;
+    type RevealProof;
+    /// Key revealed by each player after the hand to audit it.
+    type SecretKey: Clone + BorshDeserialize + BorshSerialize + Serialize;
+
+    /// Whether `public_key` can be used to encrypt cards.
+    fn is_valid_public_key(public_key: &Self::PublicKey) -> bool;
+
+    /// Cards before being shuffled.
+    fn initial_cards(num_cards: u64) -> Vec<Self::Card>;
+
+    /// Verify that `new_cards` is a permutation of `previous` encrypted by `player_id`.
+    /// `public_keys` are the keys of every player in the deck.
+    fn verify_shuffle(
+        public_keys: &[Self::PublicKey],
+        player_id: PlayerId,
+        previous: &[Self::Card],
+        new_cards: &[Self::Card],
+        proof: &Self::ShuffleProof,
+    ) -> bool;
+
+    /// Card that results from removing the encryption of the owner of `public_key` from
+    /// `progress` using `part`. None if `proof` doesn't show that `part` was computed with
+    /// the secret key of `public_key`.
+    fn reveal(
+        public_key: &Self::PublicKey,
+        progress: &Self::Card,
+        part: &Self::RevealPart,
+        proof: &Self::RevealProof,
+    ) -> Option<Self::Card>;
+
+    /// Whether `secret_key` is the secret key of `public_key`.
+    fn is_secret_key(public_key: &Self::PublicKey, secret_key: &Self::SecretKey) -> bool;
+
+    /// Check with the secret keys revealed by the players that `new_cards`, submitted by
+    /// `player_id`, hold the same cards as `previous`. None if a key needed to check it
+    /// is not revealed.
+    fn replay_shuffle(
+        secret_keys: &[Option<Self::SecretKey>],
+        player_id: PlayerId,
+        previous: &[Self::Card],
+        new_cards: &[Self::Card],
+    ) -> Option<bool>;
+
+    /// Check that `part` was computed from `progress` with `secret_key`.
+    fn replay_reveal(
+        secret_key: &Self::SecretKey,
+        progress: &Self::Card,
+        part: &Self::RevealPart,
+    ) -> bool;
+
+    /// Card encoded by a fully revealed card. None if it is not the encoding of a card
+    /// lower than `num_cards`.
+    fn decode_card(card: &Self::Card, num_cards: u64) -> Option<u64>;
+}
+
+/// Scheme used to encrypt cards in a room. It is fixed when the room is created.
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug,
+)]
+pub enum CardScheme {
+    /// Commutative encryption by exponentiation modulo a safe prime.
+    Sra,
+    /// Threshold ElGamal over Ristretto255.
+    ElGamal,
+}
+
+impl Default for CardScheme {
+    fn default() -> Self {
+        Self::ElGamal
+    }
+}
+
+/// Public key of a player in the scheme used by the room.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+pub enum PublicKey {
+    Sra(GroupElement),
+    ElGamal(elgamal::PublicKey),
+}
+
+/// Encrypted cards in the scheme used by the room.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+pub enum Cards {
+    Sra(Vec<GroupElement>),
+    ElGamal(Vec<elgamal::Ciphertext>),
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+pub enum ShuffleProof {
+    Sra(sra::ShuffleProof),
+    ElGamal(elgamal::ShuffleProof),
+}
+
+/// Part of a revealed card submitted by a player. With SRA it is the card decrypted with
+/// the key of the player. With ElGamal it is the decryption share of the player.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+pub enum RevealPart {
+    Sra(GroupElement),
+    ElGamal(elgamal::EncodedPoint),
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+pub enum RevealProof {
+    Sra(sra::RevealProof),
+    ElGamal(elgamal::RevealProof),
+}
+
+/// Secret key used by a player in a hand, revealed once the hand is over.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+pub enum SecretKey {
+    Sra(U256),
+    ElGamal(elgamal::EncodedScalar),
+}
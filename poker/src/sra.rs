@@ -0,0 +1,758 @@
+use crate::bigint::{Modulus, U256};
+use crate::scheme::Scheme;
+use crate::types::{GroupElement, PlayerId};
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_bindgen::env;
+use serde::{Deserialize, Serialize};
+
+/// Safe prime `2^256 - 36113`. Cards are encrypted in the subgroup of quadratic residues
+/// modulo this prime, which has prime order `ORDER`. A player with secret key `k`
+/// encrypts a card `c` as `c^k`, and removes its encryption from a card `d` computing
+/// `d^(k^-1)`, where `k^-1` is the inverse of `k` modulo `ORDER`.
+/// Should be kept in sync with the client.
+pub const MODULUS: Modulus = Modulus {
+    value: U256([
+        0xffff_ffff_ffff_72ef,
+        0xffff_ffff_ffff_ffff,
+        0xffff_ffff_ffff_ffff,
+        0xffff_ffff_ffff_ffff,
+    ]),
+    inv: 0x31d7_7e87_7094_03f1,
+    r2: U256([0x4dbb_bb21, 0, 0, 0]),
+};
+
+/// Order of the group, `(MODULUS - 1) / 2`.
+pub const ORDER: U256 = U256([
+    0xffff_ffff_ffff_b977,
+    0xffff_ffff_ffff_ffff,
+    0xffff_ffff_ffff_ffff,
+    0x7fff_ffff_ffff_ffff,
+]);
+
+/// Arithmetic modulo `ORDER`, used for exponents.
+pub const ORDER_MODULUS: Modulus = Modulus {
+    value: ORDER,
+    inv: 0x5eca_23ec_e393_0fb9,
+    r2: U256([0x4dbc_d544, 0, 0, 0]),
+};
+
+/// Cards are shifted by this amount before being encoded in the group, since 0 and 1
+/// are fixed points of the exponentiation.
+pub const CARD_OFFSET: u64 = 2;
+
+/// Generator of the group. Public keys are `GENERATOR^k`.
+pub const GENERATOR: U256 = U256([2, 0, 0, 0]);
+
+pub fn mul_mod(a: &U256, b: &U256) -> U256 {
+    MODULUS.mul(a, b)
+}
+
+pub fn pow_mod(base: &U256, exponent: &U256) -> U256 {
+    MODULUS.pow(base, exponent)
+}
+
+/// Whether `value` is an element of the group, that is, a quadratic residue.
+pub fn is_element(value: &U256) -> bool {
+    *value < MODULUS.value && MODULUS.is_square(value)
+}
+
+/// Encoding of `card` in the group, `(card + CARD_OFFSET)^2`.
+pub fn encode_card(card: u64) -> GroupElement {
+    let value = U256::from(card + CARD_OFFSET);
+    mul_mod(&value, &value)
+}
+
+/// Card encoded by `value`. None if it is not the encoding of a card lower than `num_cards`.
+pub fn decode_card(value: &GroupElement, num_cards: u64) -> Option<u64> {
+    (0..num_cards).find(|&card| encode_card(card) == *value)
+}
+
+/// Shuffle argument of Terelius and Wikström that the new deck is a permutation of the
+/// previous deck encrypted with the secret key `k` of the public key of the player,
+/// `new[i] = previous[p(i)]^k`. Its size and the cost to verify it grow linearly with the
+/// number of cards `n`.
+///
+/// Values are written as 32 bytes little endian when hashed. `g` is `GENERATOR`, and `h`,
+/// `h_0..h_(n-1)` are the `commitment_generators`. Exponents are taken modulo `ORDER`.
+/// - `permutation_commitments[j] = g^r_j * h_i` where `p(i) = j`, with random `r_j`.
+/// - `seed` is the sha256 of the public key, the previous deck, the new deck and the
+///   permutation commitments. Challenge `u_j` is the first 16 bytes of the sha256 of
+///   `seed` followed by `j` as 8 bytes, and `v_i = u_p(i)`.
+/// - `chain[i] = g^s_i * chain[i - 1]^v_i` with random `s_i`, where `chain[-1] = h`.
+/// - With random `w_1..w_4`, `w'_i` and `w''_i`, and `a = prod(previous[j]^u_j)`,
+///   `commitments` are `t_1 = g^w_1`, `t_2 = g^w_2`, `t_3 = g^w_3 * prod(h_i^w''_i)`,
+///   `t_4 = prod(new[i]^w''_i) * a^-w_4` and `t_5 = g^w_4`, and
+///   `chain_commitments[i] = g^w'_i * chain[i - 1]^w''_i`.
+/// - Challenge `c` is the first 16 bytes of the sha256 of `seed`, the chain, the
+///   commitments and the chain commitments.
+/// - `responses` are `w_1 + c * sum(r_j)`, `w_2 + c * sum(s_i * v_(i+1) * .. * v_(n-1))`,
+///   `w_3 + c * sum(r_j * u_j)` and `w_4 + c * k`. `chain_responses[i] = w'_i + c * s_i`
+///   and `permuted_responses[i] = w''_i + c * v_i`.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+pub struct ShuffleProof {
+    pub permutation_commitments: Vec<GroupElement>,
+    pub chain: Vec<GroupElement>,
+    pub commitments: Vec<GroupElement>,
+    pub chain_commitments: Vec<GroupElement>,
+    pub responses: Vec<U256>,
+    pub chain_responses: Vec<U256>,
+    pub permuted_responses: Vec<U256>,
+}
+
+fn challenge(parts: &[&[GroupElement]]) -> Vec<u8> {
+    let transcript = parts
+        .iter()
+        .map(|part| {
+            part.iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .collect::<Vec<_>>()
+        .join(";");
+    env::sha256(transcript.as_bytes())
+}
+
+/// sha256 of `seed` followed by every value as 32 bytes little endian.
+fn hash_values(seed: &[u8], parts: &[&[U256]]) -> Vec<u8> {
+    let mut bytes = seed.to_vec();
+    for value in parts.iter().flat_map(|part| part.iter()) {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    env::sha256(&bytes)
+}
+
+/// First 16 bytes, little endian, of the sha256 of `seed` followed by `index` as 8 bytes
+/// little endian.
+fn derive_challenge(seed: &[u8], index: u64) -> U256 {
+    let mut bytes = seed.to_vec();
+    bytes.extend_from_slice(&index.to_le_bytes());
+    let mut challenge = [0u8; 16];
+    challenge.copy_from_slice(&env::sha256(&bytes)[..16]);
+    U256::from(u128::from_le_bytes(challenge))
+}
+
+/// Generators `h, h_0, .., h_(count-1)` used to commit to a permutation, with no known
+/// relation between them. Generator `i` is the square of the sha256 of
+/// `poker shuffle generator` followed by `i` as 8 bytes little endian.
+pub fn commitment_generators(count: usize) -> Vec<GroupElement> {
+    (0..=count as u64)
+        .map(|index| {
+            let mut bytes = b"poker shuffle generator".to_vec();
+            bytes.extend_from_slice(&index.to_le_bytes());
+            let mut hash = [0u8; 32];
+            hash.copy_from_slice(&env::sha256(&bytes));
+            let value = U256::from_le_bytes(&hash);
+            mul_mod(&value, &value)
+        })
+        .collect()
+}
+
+fn neg(value: &U256) -> U256 {
+    ORDER_MODULUS.sub(&U256::ZERO, value)
+}
+
+/// Verify that `new_cards` is a permutation of `previous` encrypted with the secret key
+/// of `public_key`. Every equation of the argument is checked at once, combining them
+/// with random weights in a single `multi_pow`.
+pub fn verify_shuffle(
+    public_key: &GroupElement,
+    previous: &[GroupElement],
+    new_cards: &[GroupElement],
+    proof: &ShuffleProof,
+) -> bool {
+    let n = previous.len();
+    if n == 0
+        || new_cards.len() != n
+        || proof.permutation_commitments.len() != n
+        || proof.chain.len() != n
+        || proof.commitments.len() != 5
+        || proof.chain_commitments.len() != n
+        || proof.responses.len() != 4
+        || proof.chain_responses.len() != n
+        || proof.permuted_responses.len() != n
+    {
+        return false;
+    }
+
+    // Elements outside of the group would let a proof hold only up to a sign.
+    let elements_valid = new_cards
+        .iter()
+        .chain(proof.permutation_commitments.iter())
+        .chain(proof.chain.iter())
+        .chain(proof.commitments.iter())
+        .chain(proof.chain_commitments.iter())
+        .all(is_element);
+    let responses_valid = proof
+        .responses
+        .iter()
+        .chain(proof.chain_responses.iter())
+        .chain(proof.permuted_responses.iter())
+        .all(|response| *response < ORDER);
+    if !elements_valid || !responses_valid {
+        return false;
+    }
+
+    let generators = commitment_generators(n);
+    let seed = hash_values(
+        &[],
+        &[
+            &[*public_key],
+            previous,
+            new_cards,
+            &proof.permutation_commitments,
+        ],
+    );
+    let u = (0..n as u64)
+        .map(|j| derive_challenge(&seed, j))
+        .collect::<Vec<_>>();
+    let challenge_hash = hash_values(
+        &seed,
+        &[&proof.chain, &proof.commitments, &proof.chain_commitments],
+    );
+    let c = derive_challenge(&challenge_hash, 0);
+    let weights_seed = hash_values(
+        &challenge_hash,
+        &[
+            &proof.responses,
+            &proof.chain_responses,
+            &proof.permuted_responses,
+        ],
+    );
+    // Weights of the equations. The first one has weight 1.
+    let alpha = (0..4)
+        .map(|k| derive_challenge(&weights_seed, k))
+        .collect::<Vec<_>>();
+    let beta = (0..n as u64)
+        .map(|i| derive_challenge(&weights_seed, 4 + i))
+        .collect::<Vec<_>>();
+
+    let mul = |a: &U256, b: &U256| ORDER_MODULUS.mul(a, b);
+    let add = |a: &U256, b: &U256| ORDER_MODULUS.add(a, b);
+    let z = &proof.responses;
+    let z_chain = &proof.chain_responses;
+    let z_permuted = &proof.permuted_responses;
+
+    let mut g_exponent = add(
+        &add(&z[0], &mul(&alpha[0], &z[1])),
+        &add(&mul(&alpha[1], &z[2]), &mul(&alpha[3], &z[3])),
+    );
+    for (weight, response) in beta.iter().zip(z_chain.iter()) {
+        g_exponent = add(&g_exponent, &mul(weight, response));
+    }
+    let u_product = u.iter().fold(U256::ONE, |product, u_j| mul(&product, u_j));
+    let alpha_c = alpha
+        .iter()
+        .map(|weight| mul(weight, &c))
+        .collect::<Vec<_>>();
+    let beta_c = beta
+        .iter()
+        .map(|weight| mul(weight, &c))
+        .collect::<Vec<_>>();
+
+    let mut terms = vec![
+        (GENERATOR, g_exponent),
+        (
+            generators[0],
+            add(
+                &mul(&alpha_c[0], &u_product),
+                &mul(&beta[0], &z_permuted[0]),
+            ),
+        ),
+        (proof.commitments[0], neg(&U256::ONE)),
+        (proof.commitments[1], neg(&alpha[0])),
+        (proof.commitments[2], neg(&alpha[1])),
+        (proof.commitments[3], neg(&alpha[2])),
+        (proof.commitments[4], neg(&alpha[3])),
+        (*public_key, neg(&alpha_c[3])),
+    ];
+    let a_exponent = neg(&mul(&alpha[2], &z[3]));
+    for i in 0..n {
+        terms.push((generators[i + 1], add(&c, &mul(&alpha[1], &z_permuted[i]))));
+        terms.push((
+            proof.permutation_commitments[i],
+            neg(&add(&c, &mul(&alpha_c[1], &u[i]))),
+        ));
+        let chain_exponent = if i + 1 < n {
+            ORDER_MODULUS.sub(&mul(&beta[i + 1], &z_permuted[i + 1]), &beta_c[i])
+        } else {
+            neg(&add(&alpha_c[0], &beta_c[i]))
+        };
+        terms.push((proof.chain[i], chain_exponent));
+        terms.push((proof.chain_commitments[i], neg(&beta[i])));
+        terms.push((new_cards[i], mul(&alpha[2], &z_permuted[i])));
+        terms.push((previous[i], mul(&a_exponent, &u[i])));
+    }
+
+    MODULUS.multi_pow(&terms) == U256::ONE
+}
+
+/// Chaum-Pedersen proof that a partially decrypted card `part` was obtained with the
+/// secret key `k` of the player, that is, `progress = part^k` and `public_key = GENERATOR^k`.
+///
+/// The prover picks a random `w` and commits with `base_commitment = GENERATOR^w` and
+/// `card_commitment = part^w`. The challenge `e` is the first 16 bytes, little endian, of
+/// the sha256 of `GENERATOR`, the public key, `part`, `progress` and both commitments
+/// in decimal separated by `,`. The response is `w + e * k` modulo `ORDER`.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+pub struct RevealProof {
+    pub base_commitment: GroupElement,
+    pub card_commitment: GroupElement,
+    pub response: U256,
+}
+
+/// Verify that `part` is `progress` decrypted with the secret key of `public_key`.
+pub fn verify_reveal(
+    public_key: &GroupElement,
+    progress: &GroupElement,
+    part: &GroupElement,
+    proof: &RevealProof,
+) -> bool {
+    if !is_element(part) {
+        return false;
+    }
+
+    let hash = challenge(&[&[
+        GENERATOR,
+        *public_key,
+        *part,
+        *progress,
+        proof.base_commitment,
+        proof.card_commitment,
+    ]]);
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&hash[..16]);
+    let challenge = U256::from(u128::from_le_bytes(bytes));
+
+    pow_mod(&GENERATOR, &proof.response)
+        == mul_mod(&proof.base_commitment, &pow_mod(public_key, &challenge))
+        && pow_mod(part, &proof.response)
+            == mul_mod(&proof.card_commitment, &pow_mod(progress, &challenge))
+}
+
+/// Commutative encryption of cards by exponentiation in the group of quadratic residues.
+#[derive(Clone, Default)]
+pub struct Sra;
+
+impl Scheme for Sra {
+    type PublicKey = GroupElement;
+    type Card = GroupElement;
+    type ShuffleProof = ShuffleProof;
+    type RevealPart = GroupElement;
+    type RevealProof = RevealProof;
+    type SecretKey = U256;
+
+    fn is_valid_public_key(public_key: &GroupElement) -> bool {
+        is_element(public_key) && *public_key != U256::ONE
+    }
+
+    fn initial_cards(num_cards: u64) -> Vec<GroupElement> {
+        (0..num_cards).map(encode_card).collect()
+    }
+
+    fn verify_shuffle(
+        public_keys: &[GroupElement],
+        player_id: PlayerId,
+        previous: &[GroupElement],
+        new_cards: &[GroupElement],
+        proof: &ShuffleProof,
+    ) -> bool {
+        verify_shuffle(&public_keys[player_id as usize], previous, new_cards, proof)
+    }
+
+    fn reveal(
+        public_key: &GroupElement,
+        progress: &GroupElement,
+        part: &GroupElement,
+        proof: &RevealProof,
+    ) -> Option<GroupElement> {
+        if verify_reveal(public_key, progress, part, proof) {
+            Some(*part)
+        } else {
+            None
+        }
+    }
+
+    fn is_secret_key(public_key: &GroupElement, secret_key: &U256) -> bool {
+        !secret_key.is_zero()
+            && *secret_key < ORDER
+            && pow_mod(&GENERATOR, secret_key) == *public_key
+    }
+
+    fn replay_shuffle(
+        secret_keys: &[Option<U256>],
+        player_id: PlayerId,
+        previous: &[GroupElement],
+        new_cards: &[GroupElement],
+    ) -> Option<bool> {
+        let secret_key = secret_keys[player_id as usize].as_ref()?;
+        let mut expected = previous
+            .iter()
+            .map(|card| pow_mod(card, secret_key))
+            .collect::<Vec<_>>();
+        let mut new_cards = new_cards.to_vec();
+        expected.sort();
+        new_cards.sort();
+        Some(expected == new_cards)
+    }
+
+    fn replay_reveal(secret_key: &U256, progress: &GroupElement, part: &GroupElement) -> bool {
+        pow_mod(part, secret_key) == *progress
+    }
+
+    fn decode_card(card: &GroupElement, num_cards: u64) -> Option<u64> {
+        decode_card(card, num_cards)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::test_utils::{set_signer, Rng};
+    use std::time::Instant;
+
+    pub fn random_key(rng: &mut Rng) -> U256 {
+        let mut bytes = rng.bytes();
+        bytes[31] &= 0x3f;
+        U256::from_le_bytes(&bytes)
+    }
+
+    fn random_keys(rng: &mut Rng, count: usize) -> Vec<U256> {
+        (0..count).map(|_| random_key(rng)).collect()
+    }
+
+    /// Shuffle `previous` with `key` and prove it as the client does.
+    pub fn shuffle(
+        rng: &mut Rng,
+        key: &U256,
+        previous: &[GroupElement],
+    ) -> (Vec<GroupElement>, ShuffleProof) {
+        let n = previous.len();
+        let mul = |a: &U256, b: &U256| ORDER_MODULUS.mul(a, b);
+        let add = |a: &U256, b: &U256| ORDER_MODULUS.add(a, b);
+        let permutation = rng.permutation(n);
+        let new_cards = permutation
+            .iter()
+            .map(|&j| pow_mod(&previous[j as usize], key))
+            .collect::<Vec<_>>();
+        let generators = commitment_generators(n);
+
+        let r = random_keys(rng, n);
+        let mut permutation_commitments = vec![U256::ZERO; n];
+        for (i, &j) in permutation.iter().enumerate() {
+            permutation_commitments[j as usize] =
+                mul_mod(&pow_mod(&GENERATOR, &r[j as usize]), &generators[i + 1]);
+        }
+
+        let public_key = pow_mod(&GENERATOR, key);
+        let seed = hash_values(
+            &[],
+            &[
+                &[public_key],
+                previous,
+                &new_cards,
+                &permutation_commitments,
+            ],
+        );
+        let u = (0..n as u64)
+            .map(|j| derive_challenge(&seed, j))
+            .collect::<Vec<_>>();
+        let v = permutation
+            .iter()
+            .map(|&j| u[j as usize])
+            .collect::<Vec<_>>();
+
+        let s = random_keys(rng, n);
+        let mut chain = vec![];
+        for i in 0..n {
+            let last = chain.last().unwrap_or(&generators[0]);
+            let link = mul_mod(&pow_mod(&GENERATOR, &s[i]), &pow_mod(last, &v[i]));
+            chain.push(link);
+        }
+
+        let w = random_keys(rng, 4);
+        let w_chain = random_keys(rng, n);
+        let w_permuted = random_keys(rng, n);
+        let a = MODULUS.multi_pow(
+            &previous
+                .iter()
+                .cloned()
+                .zip(u.iter().cloned())
+                .collect::<Vec<_>>(),
+        );
+        let commitments = vec![
+            pow_mod(&GENERATOR, &w[0]),
+            pow_mod(&GENERATOR, &w[1]),
+            mul_mod(
+                &pow_mod(&GENERATOR, &w[2]),
+                &MODULUS.multi_pow(
+                    &generators[1..]
+                        .iter()
+                        .cloned()
+                        .zip(w_permuted.iter().cloned())
+                        .collect::<Vec<_>>(),
+                ),
+            ),
+            mul_mod(
+                &MODULUS.multi_pow(
+                    &new_cards
+                        .iter()
+                        .cloned()
+                        .zip(w_permuted.iter().cloned())
+                        .collect::<Vec<_>>(),
+                ),
+                &pow_mod(&a, &neg(&w[3])),
+            ),
+            pow_mod(&GENERATOR, &w[3]),
+        ];
+        let chain_commitments = (0..n)
+            .map(|i| {
+                let last = if i == 0 {
+                    &generators[0]
+                } else {
+                    &chain[i - 1]
+                };
+                mul_mod(
+                    &pow_mod(&GENERATOR, &w_chain[i]),
+                    &pow_mod(last, &w_permuted[i]),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let challenge_hash = hash_values(&seed, &[&chain, &commitments, &chain_commitments]);
+        let c = derive_challenge(&challenge_hash, 0);
+
+        let r_sum = r.iter().fold(U256::ZERO, |sum, r_j| add(&sum, r_j));
+        let chain_randomness = s
+            .iter()
+            .zip(v.iter())
+            .fold(U256::ZERO, |sum, (s_i, v_i)| add(&mul(&sum, v_i), s_i));
+        let r_weighted = r
+            .iter()
+            .zip(u.iter())
+            .fold(U256::ZERO, |sum, (r_j, u_j)| add(&sum, &mul(r_j, u_j)));
+
+        let proof = ShuffleProof {
+            permutation_commitments,
+            chain,
+            commitments,
+            chain_commitments,
+            responses: vec![
+                add(&w[0], &mul(&c, &r_sum)),
+                add(&w[1], &mul(&c, &chain_randomness)),
+                add(&w[2], &mul(&c, &r_weighted)),
+                add(&w[3], &mul(&c, key)),
+            ],
+            chain_responses: w_chain
+                .iter()
+                .zip(s.iter())
+                .map(|(w_i, s_i)| add(w_i, &mul(&c, s_i)))
+                .collect(),
+            permuted_responses: w_permuted
+                .iter()
+                .zip(v.iter())
+                .map(|(w_i, v_i)| add(w_i, &mul(&c, v_i)))
+                .collect(),
+        };
+        (new_cards, proof)
+    }
+
+    /// Remove the encryption of `key` from `progress` and prove it as the client does.
+    pub fn reveal(
+        rng: &mut Rng,
+        key: &U256,
+        progress: &GroupElement,
+    ) -> (GroupElement, RevealProof) {
+        let inverse = ORDER_MODULUS.pow(key, &ORDER.overflowing_sub(&U256::from(2u64)).0);
+        let part = pow_mod(progress, &inverse);
+        let w = random_key(rng);
+        let base_commitment = pow_mod(&GENERATOR, &w);
+        let card_commitment = pow_mod(&part, &w);
+        let hash = challenge(&[&[
+            GENERATOR,
+            pow_mod(&GENERATOR, key),
+            part,
+            *progress,
+            base_commitment,
+            card_commitment,
+        ]]);
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&hash[..16]);
+        let challenge = U256::from(u128::from_le_bytes(bytes));
+        let proof = RevealProof {
+            base_commitment,
+            card_commitment,
+            response: ORDER_MODULUS.add(&w, &ORDER_MODULUS.mul(&challenge, key)),
+        };
+        (part, proof)
+    }
+
+    #[test]
+    fn verify_valid_shuffle() {
+        set_signer("alice");
+        let mut rng = Rng::new(1);
+        let key = random_key(&mut rng);
+        let previous = Sra::initial_cards(52);
+        let (new_cards, proof) = shuffle(&mut rng, &key, &previous);
+
+        assert!(verify_shuffle(
+            &pow_mod(&GENERATOR, &key),
+            &previous,
+            &new_cards,
+            &proof
+        ));
+        assert_eq!(
+            Sra::replay_shuffle(&[Some(key)], 0, &previous, &new_cards),
+            Some(true)
+        );
+        assert_eq!(Sra::replay_shuffle(&[None], 0, &previous, &new_cards), None);
+
+        // A second shuffle starts from cards that are already encrypted.
+        let other_key = random_key(&mut rng);
+        let (shuffled_again, proof) = shuffle(&mut rng, &other_key, &new_cards);
+        assert!(verify_shuffle(
+            &pow_mod(&GENERATOR, &other_key),
+            &new_cards,
+            &shuffled_again,
+            &proof
+        ));
+    }
+
+    #[test]
+    fn reject_shuffle_with_other_key() {
+        set_signer("alice");
+        let mut rng = Rng::new(2);
+        let key = random_key(&mut rng);
+        let previous = Sra::initial_cards(52);
+        let (new_cards, proof) = shuffle(&mut rng, &key, &previous);
+        let other_key = random_key(&mut rng);
+
+        assert!(!verify_shuffle(
+            &pow_mod(&GENERATOR, &other_key),
+            &previous,
+            &new_cards,
+            &proof
+        ));
+        assert_eq!(
+            Sra::replay_shuffle(&[Some(other_key)], 0, &previous, &new_cards),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn reject_modified_shuffle() {
+        set_signer("alice");
+        let mut rng = Rng::new(3);
+        let key = random_key(&mut rng);
+        let previous = Sra::initial_cards(52);
+        let (mut new_cards, proof) = shuffle(&mut rng, &key, &previous);
+        new_cards[0] = new_cards[1];
+
+        assert!(!verify_shuffle(
+            &pow_mod(&GENERATOR, &key),
+            &previous,
+            &new_cards,
+            &proof
+        ));
+        assert_eq!(
+            Sra::replay_shuffle(&[Some(key)], 0, &previous, &new_cards),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn reject_modified_proof() {
+        set_signer("alice");
+        let mut rng = Rng::new(4);
+        let key = random_key(&mut rng);
+        let public_key = pow_mod(&GENERATOR, &key);
+        let previous = Sra::initial_cards(52);
+        let (new_cards, proof) = shuffle(&mut rng, &key, &previous);
+        assert!(verify_shuffle(&public_key, &previous, &new_cards, &proof));
+
+        let modifications: Vec<fn(&mut ShuffleProof)> = vec![
+            |proof| proof.responses[1] = ORDER_MODULUS.add(&proof.responses[1], &U256::ONE),
+            |proof| {
+                proof.chain_responses[7] = ORDER_MODULUS.add(&proof.chain_responses[7], &U256::ONE)
+            },
+            |proof| {
+                proof.permuted_responses[51] =
+                    ORDER_MODULUS.add(&proof.permuted_responses[51], &U256::ONE)
+            },
+            |proof| proof.chain.swap(3, 4),
+            |proof| proof.permutation_commitments.swap(0, 1),
+            |proof| proof.commitments[4] = mul_mod(&proof.commitments[4], &GENERATOR),
+            |proof| proof.responses.push(U256::ZERO),
+        ];
+        for modify in modifications {
+            let mut modified = proof.clone();
+            modify(&mut modified);
+            assert!(!verify_shuffle(
+                &public_key,
+                &previous,
+                &new_cards,
+                &modified
+            ));
+        }
+
+        // Negated cards are not in the group, and would make the proof hold up to a sign.
+        let mut negated = new_cards.clone();
+        negated[0] = MODULUS.value.overflowing_sub(&negated[0]).0;
+        assert!(!is_element(&negated[0]));
+        assert!(!verify_shuffle(&public_key, &previous, &negated, &proof));
+    }
+
+    #[test]
+    fn verify_reveal_part() {
+        set_signer("alice");
+        let mut rng = Rng::new(6);
+        let key = random_key(&mut rng);
+        let public_key = pow_mod(&GENERATOR, &key);
+        let progress = pow_mod(&encode_card(7), &key);
+        let (part, proof) = reveal(&mut rng, &key, &progress);
+
+        assert_eq!(part, encode_card(7));
+        assert_eq!(
+            Sra::reveal(&public_key, &progress, &part, &proof),
+            Some(part)
+        );
+        assert!(Sra::replay_reveal(&key, &progress, &part));
+
+        let other_key = random_key(&mut rng);
+        let other_public_key = pow_mod(&GENERATOR, &other_key);
+        assert_eq!(
+            Sra::reveal(&other_public_key, &progress, &part, &proof),
+            None
+        );
+        assert!(!Sra::replay_reveal(&other_key, &progress, &part));
+
+        let (wrong_part, wrong_proof) = reveal(&mut rng, &other_key, &progress);
+        assert_eq!(
+            Sra::reveal(&public_key, &progress, &wrong_part, &wrong_proof),
+            None
+        );
+    }
+
+    /// Time to verify a shuffle of a full deck. Run with
+    /// `cargo test --release -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_verify_shuffle() {
+        set_signer("alice");
+        let mut rng = Rng::new(5);
+        let key = random_key(&mut rng);
+        let previous = Sra::initial_cards(52);
+        let (new_cards, proof) = shuffle(&mut rng, &key, &previous);
+        let public_key = pow_mod(&GENERATOR, &key);
+
+        let start = Instant::now();
+        assert!(verify_shuffle(&public_key, &previous, &new_cards, &proof));
+        println!("SRA shuffle of 52 cards verified in {:?}", start.elapsed());
+
+        let start = Instant::now();
+        for _ in 0..100 {
+            pow_mod(&public_key, &key);
+        }
+        println!("100 exponentiations in {:?}", start.elapsed());
+    }
+}
@@ -0,0 +1,102 @@
+use crate::types::Balance;
+use near_bindgen::MockedBlockchain;
+use near_bindgen::{env, testing_env, PromiseResult, RuntimeFeesConfig, VMConfig, VMContext};
+
+pub fn get_context(signer_account_id: &str) -> VMContext {
+    VMContext {
+        current_account_id: "poker".to_string(),
+        signer_account_id: signer_account_id.to_string(),
+        signer_account_pk: vec![0, 1, 2],
+        predecessor_account_id: signer_account_id.to_string(),
+        input: vec![],
+        block_index: 0,
+        block_timestamp: 0,
+        // Enough to pay back the deposits held by the contract.
+        account_balance: 10u128.pow(30),
+        account_locked_balance: 0,
+        storage_usage: 0,
+        attached_deposit: 0,
+        prepaid_gas: 10u64.pow(18),
+        random_seed: vec![0, 1, 2],
+        is_view: false,
+        output_data_receivers: vec![],
+    }
+}
+
+/// Set up the mocked blockchain with `signer_account_id` as signer and predecessor.
+pub fn set_signer(signer_account_id: &str) {
+    testing_env!(get_context(signer_account_id));
+}
+
+/// Set up the mocked blockchain as in `set_signer`, at block `block_index`.
+pub fn set_signer_at(signer_account_id: &str, block_index: u64) {
+    let mut context = get_context(signer_account_id);
+    context.block_index = block_index;
+    testing_env!(context);
+}
+
+/// Set up the mocked blockchain for a call from `predecessor_account_id`, which can be a
+/// contract called by `signer_account_id`, attaching `deposit`. As on chain, the deposit
+/// is part of the balance of the contract, so refunds can be checked with
+/// `env::account_balance`.
+pub fn set_call(signer_account_id: &str, predecessor_account_id: &str, deposit: Balance) {
+    let mut context = get_context(signer_account_id);
+    context.predecessor_account_id = predecessor_account_id.to_string();
+    context.attached_deposit = deposit;
+    context.account_balance = deposit;
+    testing_env!(context);
+}
+
+/// Set up the mocked blockchain as in `set_signer`, attaching `deposit`.
+pub fn set_deposit(signer_account_id: &str, deposit: Balance) {
+    set_call(signer_account_id, signer_account_id, deposit);
+}
+
+/// Set up the mocked blockchain for a callback of the contract, where the promise it
+/// waits on resulted in `result`.
+pub fn set_callback(result: PromiseResult) {
+    env::set_blockchain_interface(Box::new(MockedBlockchain::new(
+        get_context("poker"),
+        VMConfig::default(),
+        RuntimeFeesConfig::default(),
+        vec![result],
+        Default::default(),
+    )));
+}
+
+/// Deterministic generator, so that failing tests can be reproduced.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        let mut value = self.0;
+        value ^= value >> 33;
+        value = value.wrapping_mul(0xff51_afd7_ed55_8ccd);
+        value ^ (value >> 33)
+    }
+
+    pub fn bytes(&mut self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for chunk in bytes.chunks_mut(8) {
+            chunk.copy_from_slice(&self.next_u64().to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn permutation(&mut self, len: usize) -> Vec<u64> {
+        let mut permutation = (0..len as u64).collect::<Vec<_>>();
+        for i in (1..len).rev() {
+            let j = (self.next_u64() % (i as u64 + 1)) as usize;
+            permutation.swap(i, j);
+        }
+        permutation
+    }
+}
@@ -1,5 +1,53 @@
+use crate::bigint::U256;
+
 pub type PlayerId = u64;
 pub type CardId = u64;
 pub type RoomId = u64;
 pub type AccountId = String;
-pub type CryptoHash = String;
+/// Element of the group used to encrypt cards.
+pub type GroupElement = U256;
+pub type Balance = u128;
+pub type Gas = u64;
+
+/// Serialize balances as decimal strings in JSON, since numbers can't hold them exactly.
+pub mod balance_format {
+    use super::Balance;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(balance: &Balance, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&balance.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Balance, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Serialize 32 bytes values as hex strings in JSON.
+pub mod hex_format {
+    use super::to_hex;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&to_hex(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 32], D::Error> {
+        let value = String::deserialize(deserializer)?;
+        if value.len() != 64 || !value.is_ascii() {
+            return Err(de::Error::custom("Expected 32 bytes encoded in hex"));
+        }
+
+        let mut bytes = [0u8; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&value[2 * i..2 * i + 2], 16).map_err(de::Error::custom)?;
+        }
+        Ok(bytes)
+    }
+}